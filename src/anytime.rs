@@ -0,0 +1,213 @@
+use crate::board::Action;
+use crate::search::{
+    self, Best, Node, Outcome, Partial, Path, ProgressTracker, Puzzle, SearchConfig, SearchStats,
+};
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::rc::Rc;
+use std::time::Instant;
+
+/// A solution cheaper than any found before it.
+pub struct Improvement<M = Action> {
+    pub path: Vec<M>,
+    pub cost: u32,
+    /// The most times the least possible cost that `cost` could be.
+    pub bound: f64,
+}
+
+pub struct AnytimeResult<M = Action> {
+    /// `Solved` with the best solution as soon as there is one, even if a limit
+    /// stopped the search before it was proven the cheapest.
+    pub outcome: Outcome<M>,
+    /// The solution's bound, as in `Improvement`. It's 1 once the search has run out
+    /// of states that could lead to anything cheaper.
+    pub bound: Option<f64>,
+    pub stats: SearchStats,
+}
+
+impl<M> AnytimeResult<M> {
+    pub fn solution(&self) -> Option<&Vec<M>> {
+        match &self.outcome {
+            Outcome::Solved(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// The open list, keeping count of its unweighted f-values as well. The lowest of
+/// them is the least a solution through any state still open could cost.
+struct Open<S, M> {
+    heap: BinaryHeap<Node<S, M>>,
+    f_values: BTreeMap<u32, usize>,
+    reached: u64,
+}
+
+impl<S, M> Open<S, M> {
+    fn push(&mut self, node: Node<S, M>) {
+        *self.f_values.entry(node.g + node.h).or_insert(0) += 1;
+        self.heap.push(node);
+        self.reached += 1;
+    }
+
+    fn pop(&mut self) -> Option<Node<S, M>> {
+        let node = self.heap.pop()?;
+
+        let f = node.g + node.h;
+        match self.f_values.get_mut(&f) {
+            Some(count) if *count > 1 => *count -= 1,
+            _ => {
+                self.f_values.remove(&f);
+            }
+        }
+
+        Some(node)
+    }
+
+    /// How many times the least possible cost a solution costing `cost` could be,
+    /// with `lowest` the lowest f-value of anything not on the open list.
+    fn bound(&self, cost: u32, lowest: u32) -> f64 {
+        let lower = self
+            .f_values
+            .keys()
+            .next()
+            .map_or(cost, |f| *f.min(&cost))
+            .min(lowest);
+
+        if lower == 0 {
+            1.0
+        } else {
+            f64::from(cost) / f64::from(lower)
+        }
+    }
+}
+
+/// Anytime weighted A*: searches with the heuristic weighted by `config.weight` to
+/// find a solution quickly, then carries on looking for cheaper ones. Once there's a
+/// solution, states that couldn't lead to a cheaper one are dropped, and a state
+/// reached more cheaply than it was expanded at is expanded again. `improved` is
+/// called with each new best solution, so a caller can use one as soon as it's good
+/// enough, and a limit stops the search with the best solution so far.
+pub fn find_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+    mut improved: impl FnMut(&Improvement<P::Move>),
+) -> AnytimeResult<P::Move> {
+    let state_bytes = 2 * size_of::<usize>() + puzzle.state_size(start);
+    let start_time = Instant::now();
+    let mut tracker = ProgressTracker::create(config, Default::default());
+
+    let mut open = Open {
+        heap: BinaryHeap::new(),
+        f_values: BTreeMap::new(),
+        reached: 0,
+    };
+    let h = puzzle.heuristic(start);
+    open.push(Node {
+        state: Rc::new(start.clone()),
+        path: Rc::new(Path::None),
+        h,
+        g: 0,
+        f: config.f(0, h),
+        rank: config.tie_break.rank(puzzle, start, h),
+        order: config.tie_break.order(0),
+    });
+
+    // the least cost each state has been expanded at
+    let mut expanded: HashMap<Rc<P::State>, u32> = HashMap::new();
+    let mut solution: Option<(u32, Rc<Path<P::Move>>)> = None;
+    // lowest (h, g) node seen so far, reported as the partial solution if a limit is hit
+    let mut best: Option<Best<P::Move>> = None;
+    let mut stopped = None;
+
+    while let Some(node) = open.pop() {
+        let cost = solution.as_ref().map(|(cost, _)| *cost);
+        if cost.is_some_and(|cost| node.g + node.h >= cost) {
+            continue;
+        }
+
+        match expanded.entry(node.state.clone()) {
+            Entry::Occupied(entry) if *entry.get() <= node.g => {
+                tracker.stats.duplicates += 1;
+                continue;
+            }
+            Entry::Occupied(mut entry) => {
+                entry.insert(node.g);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(node.g);
+            }
+        }
+
+        let memory = (expanded.len() + open.heap.len()) * state_bytes;
+        tracker.update(node.g, node.h, open.heap.len(), memory);
+
+        if puzzle.is_goal_state(&node.state) {
+            improved(&Improvement {
+                path: search::read_path(&node.path),
+                cost: node.g,
+                bound: open.bound(node.g, node.g),
+            });
+            solution = Some((node.g, node.path));
+            continue;
+        }
+
+        if best
+            .as_ref()
+            .is_none_or(|(h, g, _)| (node.h, node.g) < (*h, *g))
+        {
+            best = Some((node.h, node.g, node.path.clone()));
+        }
+
+        if let Some(limit) = search::check_limits(config, &tracker.stats, start_time) {
+            stopped = Some((limit, node.g + node.h));
+            break;
+        }
+
+        let children = puzzle.create_children_counted(&node.state, &mut tracker.stats.prunes);
+        tracker.stats.nodes_generated += children.len() as u64;
+
+        for (child, actions) in children {
+            let h = puzzle.heuristic(&child);
+            let g = node.g + puzzle.cost(&actions, config.metric);
+            if cost.is_some_and(|cost| g + h >= cost) {
+                continue;
+            }
+
+            open.push(Node {
+                f: config.f(g, h),
+                rank: config.tie_break.rank(puzzle, &child, h),
+                order: config.tie_break.order(open.reached),
+                state: Rc::new(child),
+                path: Rc::new(Path::Prev(node.path.clone(), actions)),
+                h,
+                g,
+            });
+        }
+    }
+
+    tracker.stats.solution_cost = solution.as_ref().map(|(cost, _)| *cost);
+    let (outcome, bound) = match (solution, stopped) {
+        (Some((cost, path)), Some((_, lowest))) => (
+            Outcome::Solved(search::read_path(&path)),
+            Some(open.bound(cost, lowest)),
+        ),
+        (Some((_, path)), None) => (Outcome::Solved(search::read_path(&path)), Some(1.0)),
+        (None, Some((limit, _))) => {
+            let (heuristic, _, path) = best.unwrap();
+            let partial = Partial {
+                path: search::read_path(&path),
+                heuristic,
+            };
+            (Outcome::LimitReached(limit, partial), None)
+        }
+        (None, None) => (Outcome::Unsolvable, None),
+    };
+
+    AnytimeResult {
+        outcome,
+        bound,
+        stats: tracker.finish(),
+    }
+}
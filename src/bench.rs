@@ -0,0 +1,280 @@
+use crate::board::Board;
+use crate::json::Json;
+use crate::lurd;
+use crate::search::{self, Limits, SearchConfig};
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Fraction by which the node count may grow over the baseline before it's flagged.
+const NODE_TOLERANCE: f64 = 0.10;
+
+pub struct Level {
+    pub name: String,
+    pub text: String,
+}
+
+/// Loads every level in `path`. A directory is read as one level per file,
+/// in name order; a file is read as a collection of levels separated by
+/// blank lines, each optionally preceded by a `;` comment naming it.
+pub fn load_levels(path: &Path) -> std::io::Result<Vec<Level>> {
+    if path.is_dir() {
+        let mut files: Vec<_> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, _>>()?;
+        files.retain(|file| file.is_file());
+        files.sort();
+
+        files
+            .into_iter()
+            .map(|file| {
+                Ok(Level {
+                    name: file.file_name().unwrap().to_string_lossy().into_owned(),
+                    text: std::fs::read_to_string(&file)?,
+                })
+            })
+            .collect()
+    } else {
+        Ok(split_collection(&std::fs::read_to_string(path)?))
+    }
+}
+
+/// Splits a collection into its levels, numbering any without a name from 1.
+pub fn split_collection(text: &str) -> Vec<Level> {
+    let mut levels = Vec::new();
+    let mut name = None;
+    let mut lines = Vec::new();
+
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !lines.is_empty() {
+                levels.push(Level {
+                    name: name
+                        .take()
+                        .unwrap_or_else(|| (levels.len() + 1).to_string()),
+                    text: lines.join("\n"),
+                });
+                lines.clear();
+            }
+        } else if let Some(comment) = line.strip_prefix(';') {
+            name = Some(comment.trim().to_string());
+        } else {
+            lines.push(line);
+        }
+    }
+
+    levels
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    /// One of the `Outcome::status` names, or `invalid` if the level failed to parse.
+    pub status: String,
+    pub moves: Option<usize>,
+    pub pushes: Option<usize>,
+    pub nodes: u64,
+    pub time: f64,
+}
+
+impl BenchResult {
+    pub fn is_solved(&self) -> bool {
+        self.status == "solved"
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("level", self.name.clone().into()),
+            ("status", self.status.clone().into()),
+            ("moves", self.moves.into()),
+            ("pushes", self.pushes.into()),
+            ("nodes", self.nodes.into()),
+            ("time", self.time.into()),
+        ])
+    }
+}
+
+fn solve_level(level: &Level, limits: &Limits) -> BenchResult {
+    let mut result = BenchResult {
+        name: level.name.clone(),
+        status: "invalid".to_string(),
+        moves: None,
+        pushes: None,
+        nodes: 0,
+        time: 0.0,
+    };
+
+    let (board, start) = match Board::parse_level_string(&level.text) {
+        Ok(level) => level,
+        Err(_) => return result,
+    };
+
+    let config = SearchConfig {
+        progress: false,
+        limits: limits.clone(),
+        ..SearchConfig::default()
+    };
+
+    let start_time = Instant::now();
+    let search = search::find_path(&board, &start, &config);
+    result.time = start_time.elapsed().as_secs_f64();
+
+    result.status = search.outcome.status().to_string();
+    result.nodes = search.stats.nodes_expanded;
+
+    if let Some(path) = search.solution() {
+        let solution = lurd::format(&board, &start, path);
+        result.moves = Some(solution.len());
+        result.pushes = Some(solution.chars().filter(char::is_ascii_uppercase).count());
+    }
+
+    result
+}
+
+/// Solves every level on `jobs` worker threads, returning results in the same order as `levels`.
+pub fn run(levels: &[Level], limits: &Limits, jobs: usize) -> Vec<BenchResult> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; levels.len()]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= levels.len() {
+                    break;
+                }
+
+                let result = solve_level(&levels[i], limits);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
+const CSV_HEADER: &str = "level,status,moves,pushes,nodes,time";
+
+pub fn to_csv(results: &[BenchResult]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+
+    let optional = |n: Option<usize>| n.map_or(String::new(), |n| n.to_string());
+
+    for result in results {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{:.6}\n",
+            result.name,
+            result.status,
+            optional(result.moves),
+            optional(result.pushes),
+            result.nodes,
+            result.time
+        ));
+    }
+
+    csv
+}
+
+pub fn to_json(results: &[BenchResult]) -> Json {
+    Json::Array(results.iter().map(BenchResult::to_json).collect())
+}
+
+/// Reads back a table written by `to_csv`.
+pub fn parse_csv(csv: &str) -> Result<Vec<BenchResult>, String> {
+    let mut lines = csv.lines();
+
+    if lines.next() != Some(CSV_HEADER) {
+        return Err("Baseline is missing the CSV header".to_string());
+    }
+
+    let optional = |field: &str| -> Result<Option<usize>, String> {
+        if field.is_empty() {
+            Ok(None)
+        } else {
+            field
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid count {:?}", field))
+        }
+    };
+
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            // level names may contain commas, the rest of the fields can't
+            let fields: Vec<_> = line.rsplitn(6, ',').collect();
+            if fields.len() != 6 {
+                return Err(format!("Malformed baseline row {:?}", line));
+            }
+
+            Ok(BenchResult {
+                name: fields[5].to_string(),
+                status: fields[4].to_string(),
+                moves: optional(fields[3])?,
+                pushes: optional(fields[2])?,
+                nodes: fields[1]
+                    .parse()
+                    .map_err(|_| format!("Invalid node count {:?}", fields[1]))?,
+                time: fields[0]
+                    .parse()
+                    .map_err(|_| format!("Invalid time {:?}", fields[0]))?,
+            })
+        })
+        .collect()
+}
+
+/// Describes every way `results` got worse than `baseline`, matching levels by name.
+/// A baseline level missing from `results` counts as a regression too.
+pub fn compare(baseline: &[BenchResult], results: &[BenchResult]) -> Vec<String> {
+    let mut regressions = Vec::new();
+
+    for old in baseline {
+        let new = match results.iter().find(|r| r.name == old.name) {
+            Some(new) => new,
+            None => {
+                regressions.push(format!("{}: missing from the results", old.name));
+                continue;
+            }
+        };
+
+        if old.is_solved() && !new.is_solved() {
+            regressions.push(format!("{}: no longer solved ({})", new.name, new.status));
+            continue;
+        }
+
+        if old.is_solved() && new.moves > old.moves {
+            regressions.push(format!(
+                "{}: solution grew from {} to {} moves",
+                new.name,
+                old.moves.unwrap(),
+                new.moves.unwrap()
+            ));
+        }
+
+        if old.is_solved() && new.pushes > old.pushes {
+            regressions.push(format!(
+                "{}: solution grew from {} to {} pushes",
+                new.name,
+                old.pushes.unwrap(),
+                new.pushes.unwrap()
+            ));
+        }
+
+        if old.is_solved() && new.nodes as f64 > old.nodes as f64 * (1.0 + NODE_TOLERANCE) {
+            regressions.push(format!(
+                "{}: expanded {} nodes, up from {}",
+                new.name, new.nodes, old.nodes
+            ));
+        }
+    }
+
+    regressions
+}
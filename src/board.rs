@@ -1,5 +1,8 @@
+use crate::checkpoint::{self, Persist};
+
 use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
+use std::io;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Action {
@@ -9,21 +12,131 @@ pub enum Action {
     Right,
 }
 
+impl Action {
+    pub const ALL: [Action; 4] = [Action::Up, Action::Down, Action::Left, Action::Right];
+
+    pub fn reverse(self) -> Action {
+        match self {
+            Action::Up => Action::Down,
+            Action::Down => Action::Up,
+            Action::Left => Action::Right,
+            Action::Right => Action::Left,
+        }
+    }
+
+    /// The position one step from `(x, y)` in this direction.
+    #[inline]
+    pub fn apply(self, (x, y): (u32, u32)) -> (u32, u32) {
+        match self {
+            Action::Up => (x, y - 1),
+            Action::Down => (x, y + 1),
+            Action::Left => (x - 1, y),
+            Action::Right => (x + 1, y),
+        }
+    }
+}
+
+/// Counts of pushes that `create_children` ruled out, by the rule that caught them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Prunes {
+    /// The crate would land on a tile it can never be pushed to a goal from.
+    pub dead_tile: u64,
+    /// The crates would end up frozen against a wall off their goals.
+    pub deadlock: u64,
+    /// Ruled out by a variant's own rules, such as Tiles running out of spare crates.
+    pub other: u64,
+}
+
+impl Prunes {
+    pub fn total(&self) -> u64 {
+        self.dead_tile + self.deadlock + self.other
+    }
+}
+
+// a goal's tile along with the distance from every tile to it
+pub(crate) type Goal = (usize, Box<[u32]>);
+
+// a tile the player can walk to along with the moves that get there
+type Walk = (usize, Box<[Action]>);
+
+// a level read with any number of players, as the board, the players and the crates
+type Multiplayer = (Board, Vec<usize>, Box<[bool]>);
+
+/// A level, with its tiles numbered over the area the player can reach so that every
+/// per-tile table covers the inside of the level only. Moving between tiles goes
+/// through a table of neighbours rather than arithmetic on positions.
 #[derive(Clone)]
 pub struct Board {
-    goals: Box<[((u32, u32), Box<[u32]>)]>,
+    /// The neighbouring tile in each direction of `Action::ALL`, or `None` for a wall.
+    neighbours: Box<[[Option<usize>; 4]]>,
+    /// Where each tile sits in the level, in reading order.
+    positions: Box<[(u32, u32)]>,
+    goals: Box<[Goal]>,
     goal_tiles: Box<[bool]>,
-    walls: Box<[bool]>,
     dead_tiles: Box<[bool]>,
+    pull_dead_tiles: Box<[bool]>,
+    /// Only needed for drawing the level, so kept as a list.
+    walls: Box<[(u32, u32)]>,
     width: usize,
+    height: usize,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct BoardState {
-    player: (u32, u32),
+    /// The tile the player is on.
+    player: usize,
     crates: Box<[bool]>,
 }
 
+impl BoardState {
+    /// Approximate number of bytes this state occupies, including its crate buffer.
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + std::mem::size_of_val(&*self.crates)
+    }
+}
+
+impl BoardState {
+    pub(crate) fn new(player: usize, crates: Box<[bool]>) -> BoardState {
+        BoardState { player, crates }
+    }
+
+    pub(crate) fn set_player(&mut self, player: usize) {
+        self.player = player;
+    }
+
+    /// Whether both states have crates in exactly the same places, wherever the player is.
+    pub fn same_crates(&self, other: &BoardState) -> bool {
+        self.crates == other.crates
+    }
+}
+
+impl Persist for Action {
+    fn write(&self, out: &mut Vec<u8>) {
+        (*self as u8).write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Action::ALL
+            .get(u8::read(input)? as usize)
+            .copied()
+            .ok_or_else(|| checkpoint::invalid("Checkpoint has an invalid move"))
+    }
+}
+
+impl Persist for BoardState {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.player.write(out);
+        checkpoint::write_bits(&self.crates, out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Ok(BoardState {
+            player: usize::read(input)?,
+            crates: checkpoint::read_bits(input)?,
+        })
+    }
+}
+
 impl Hash for BoardState {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.player.hash(state);
@@ -36,78 +149,124 @@ impl Hash for BoardState {
 }
 
 impl Board {
+    /// The tile at `(x, y)`, or `None` if it's a wall or outside the player's area.
+    #[inline]
+    pub(crate) fn tile(&self, (x, y): (u32, u32)) -> Option<usize> {
+        // tiles are numbered in reading order, so their positions are sorted by row
+        self.positions
+            .binary_search_by_key(&(y, x), |(x, y)| (*y, *x))
+            .ok()
+    }
+
     #[inline]
-    fn is_goal(&self, x: u32, y: u32) -> bool {
-        self.goal_tiles[y as usize * self.width + x as usize]
+    pub(crate) fn position(&self, tile: usize) -> (u32, u32) {
+        self.positions[tile]
     }
 
     #[inline]
-    fn is_empty(&self, state: &BoardState, x: u32, y: u32) -> bool {
-        !self.is_wall(x, y) && !self.is_crate(state, x, y)
+    pub(crate) fn neighbour(&self, tile: usize, action: Action) -> Option<usize> {
+        self.neighbours[tile][action as usize]
     }
 
     #[inline]
-    fn is_wall(&self, x: u32, y: u32) -> bool {
-        self.walls[y as usize * self.width + x as usize]
+    pub(crate) fn player_tile(&self, state: &BoardState) -> usize {
+        state.player
+    }
+
+    /// Where the player is in the level.
+    pub fn player_position(&self, state: &BoardState) -> (u32, u32) {
+        self.positions[state.player]
     }
 
     #[inline]
-    fn is_crate(&self, state: &BoardState, x: u32, y: u32) -> bool {
-        state.crates[y as usize * self.width + x as usize]
+    pub(crate) fn is_goal_tile(&self, tile: usize) -> bool {
+        self.goal_tiles[tile]
     }
 
     #[inline]
-    fn set_crate(&self, state: &mut BoardState, x: u32, y: u32, crate_bit: bool) {
-        state.crates[y as usize * self.width + x as usize] = crate_bit;
+    pub(crate) fn is_crate_at(&self, state: &BoardState, tile: usize) -> bool {
+        state.crates[tile]
     }
 
     #[inline]
-    fn is_dead_tile(&self, x: u32, y: u32) -> bool {
-        self.dead_tiles[y as usize * self.width + x as usize]
+    pub(crate) fn set_crate_at(&self, state: &mut BoardState, tile: usize, crate_bit: bool) {
+        state.crates[tile] = crate_bit;
+    }
+
+    pub(crate) fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    /// Width of the level as drawn, in characters.
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn tile_count(&self) -> usize {
+        self.positions.len()
     }
 
     pub fn is_goal_state(&self, state: &BoardState) -> bool {
-        for ((x, y), _) in self.goals.iter() {
-            if !self.is_crate(state, *x, *y) {
-                return false;
-            }
-        }
+        self.goals.iter().all(|(goal, _)| state.crates[*goal])
+    }
 
-        true
+    /// Number of goals with a crate on them.
+    pub fn crates_on_goals(&self, state: &BoardState) -> u32 {
+        self.goals
+            .iter()
+            .filter(|(goal, _)| state.crates[*goal])
+            .count() as u32
+    }
+
+    /// The tiles with a crate on them, in reading order.
+    pub(crate) fn crate_tiles<'a>(
+        &self,
+        state: &'a BoardState,
+    ) -> impl Iterator<Item = usize> + 'a {
+        state
+            .crates
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile)
+            .map(|(i, _)| i)
     }
 
-    fn iter_crates<'a>(&'a self, state: &'a BoardState) -> impl Iterator<Item = (u32, u32)> + 'a {
+    pub fn iter_crates<'a>(
+        &'a self,
+        state: &'a BoardState,
+    ) -> impl Iterator<Item = (u32, u32)> + 'a {
         state
             .crates
             .iter()
             .enumerate()
             .filter(|(_, tile)| **tile)
-            .map(move |(i, _)| ((i % self.width) as u32, (i / self.width) as u32))
+            .map(move |(i, _)| self.positions[i])
     }
 
     fn is_unsolvable(&self, state: &BoardState) -> bool {
-        for (x, y) in self.iter_crates(state) {
+        let is_wall = |tile, action| self.neighbour(tile, action).is_none();
+
+        for tile in (0..state.crates.len()).filter(|i| state.crates[*i]) {
             // we now check this as we move the crates
             // board is unsolvable if there is a crate on a dead tile
-            // if self.is_dead_tile(x, y) {
+            // if self.dead_tiles[tile] {
             //     return true;
             // }
 
             // board is unsolvable if there are two crates next to each other next to walls
-            if self.is_crate(state, x + 1, y)
-                && (self.is_wall(x, y - 1) || self.is_wall(x, y + 1))
-                && (self.is_wall(x + 1, y - 1) || self.is_wall(x + 1, y + 1))
-            {
-                if !(self.is_goal(x, y) && self.is_goal(x + 1, y)) {
-                    return true;
-                }
-            }
-
-            if self.is_crate(state, x, y + 1)
-                && (self.is_wall(x - 1, y) || self.is_wall(x + 1, y))
-                && (self.is_wall(x - 1, y + 1) || self.is_wall(x + 1, y + 1))
-            {
-                if !(self.is_goal(x, y) && self.is_goal(x, y + 1)) {
+            for (along, [side, other_side]) in [
+                (Action::Right, [Action::Up, Action::Down]),
+                (Action::Down, [Action::Left, Action::Right]),
+            ] {
+                let next = match self.neighbour(tile, along) {
+                    Some(next) if state.crates[next] => next,
+                    _ => continue,
+                };
+
+                if (is_wall(tile, side) || is_wall(tile, other_side))
+                    && (is_wall(next, side) || is_wall(next, other_side))
+                    && !(self.goal_tiles[tile] && self.goal_tiles[next])
+                {
                     return true;
                 }
             }
@@ -119,25 +278,22 @@ impl Board {
     pub fn heuristic(&self, state: &BoardState) -> u32 {
         let mut h = 0;
 
-        // TODO: Use Hungarian Algorithm to find optimal matching
-
         let unsat_goal_dists: Vec<_> = self
             .goals
             .iter()
-            .filter(|((x, y), _)| !self.is_crate(state, *x, *y))
+            .filter(|(goal, _)| !state.crates[*goal])
             .map(|(_, dists)| dists)
             .collect();
 
         // requires each crate to be moved to a goal
         // therefore it takes at least as many moves as it takes to move each
         // crate to the goal closest to it
-        h += self
-            .iter_crates(state)
-            .filter(|(x, y)| !self.is_goal(*x, *y))
-            .map(|(bx, by)| {
+        h += (0..state.crates.len())
+            .filter(|tile| state.crates[*tile] && !self.goal_tiles[*tile])
+            .map(|tile| {
                 unsat_goal_dists
                     .iter()
-                    .map(|dists| dists[by as usize * self.width + bx as usize])
+                    .map(|dists| dists[tile])
                     .min()
                     .unwrap()
             })
@@ -146,103 +302,414 @@ impl Board {
         h
     }
 
+    /// Generates every state reachable from `state` with a single push. Each child comes
+    /// with the moves that reach it, listed last move first.
     pub fn create_children(&self, state: &BoardState) -> Vec<(BoardState, Box<[Action]>)> {
+        self.create_children_counted(state, &mut Prunes::default())
+    }
+
+    /// `create_children`, also counting the pushes it rules out into `prunes`.
+    pub fn create_children_counted(
+        &self,
+        state: &BoardState,
+        prunes: &mut Prunes,
+    ) -> Vec<(BoardState, Box<[Action]>)> {
+        self.create_children_among(state, &[], prunes)
+    }
+
+    /// Reads the walk to `tile` back out of the step each tile was entered with, listed
+    /// last move first.
+    fn read_path(&self, paths: &[Option<Action>], tile: usize) -> Vec<Action> {
+        let mut path = vec![];
+        let mut tile = tile;
+
+        while let Some(action) = paths[tile] {
+            path.push(action);
+            tile = self
+                .neighbour(tile, action.reverse())
+                .expect("walked through a wall");
+        }
+
+        path
+    }
+
+    /// `create_children_counted` with `others` standing in the way, as other players do in Multiban.
+    pub(crate) fn create_children_among(
+        &self,
+        state: &BoardState,
+        others: &[usize],
+        prunes: &mut Prunes,
+    ) -> Vec<(BoardState, Box<[Action]>)> {
+        let is_empty = |tile: usize| !state.crates[tile] && !others.contains(&tile);
         let mut children = Vec::new();
 
-        let mut paths = vec![None; self.walls.len()];
-        let mut seen = vec![false; self.walls.len()];
+        let mut paths = vec![None; self.tile_count()];
+        let mut seen = vec![false; self.tile_count()];
         let mut queue = VecDeque::new();
 
-        let read_path = |paths: &Vec<_>, index, action| -> Box<[Action]> {
-            let mut path = vec![action];
-            let mut index = index;
+        queue.push_back((self.player_tile(state), None));
+
+        while let Some((tile, action)) = queue.pop_front() {
+            if !seen[tile] && is_empty(tile) {
+                seen[tile] = true;
+                paths[tile] = action;
+
+                for push in Action::ALL {
+                    let crate_tile = match self.neighbour(tile, push) {
+                        Some(next) if state.crates[next] => next,
+                        _ => continue,
+                    };
+
+                    let target = match self.neighbour(crate_tile, push) {
+                        Some(target) if is_empty(target) => target,
+                        _ => continue,
+                    };
+
+                    if self.dead_tiles[target] {
+                        prunes.dead_tile += 1;
+                        continue;
+                    }
 
-            while let Some(action) = paths[index] {
-                path.push(action);
+                    let mut child = state.clone();
+                    child.crates[target] = true;
+                    child.crates[crate_tile] = false;
+                    child.player = crate_tile;
+                    if self.is_unsolvable(&child) {
+                        prunes.deadlock += 1;
+                    } else {
+                        let mut path = vec![push];
+                        path.extend(self.read_path(&paths, tile));
+                        children.push((child, path.into_boxed_slice()));
+                    }
+                }
 
-                match action {
-                    Action::Up => index += self.width,
-                    Action::Down => index -= self.width,
-                    Action::Left => index += 1,
-                    Action::Right => index -= 1,
+                for step in Action::ALL {
+                    if let Some(next) = self.neighbour(tile, step) {
+                        queue.push_back((next, Some(step)));
+                    }
                 }
             }
+        }
 
-            path.into_boxed_slice()
-        };
+        children
+    }
 
-        queue.push_back((state.player.0, state.player.1, None));
+    /// Generates every state that reaches `state` with a single push, by walking the
+    /// player around its region and pulling crates backwards. Each parent comes with the
+    /// moves that take it forward to `state`, listed last move first like `create_children`.
+    pub fn create_parents(&self, state: &BoardState) -> Vec<(BoardState, Box<[Action]>)> {
+        self.pull(state, Some(state.player))
+    }
 
-        while let Some((x, y, action)) = queue.pop_front() {
-            let index = y as usize * self.width + x as usize;
+    /// Like `create_parents`, but ignores the player's position and pulls from every
+    /// region the player could be in. Used when the player is unknown, such as in the
+    /// solved configuration, so each parent's moves are just its push.
+    pub fn create_parents_any_player(
+        &self,
+        state: &BoardState,
+    ) -> Vec<(BoardState, Box<[Action]>)> {
+        self.pull(state, None)
+    }
 
-            if !seen[index] && self.is_empty(state, x, y) {
-                seen[index] = true;
-                paths[index] = action;
+    fn pull(&self, state: &BoardState, start: Option<usize>) -> Vec<(BoardState, Box<[Action]>)> {
+        let mut parents = Vec::new();
 
-                if self.is_crate(state, x, y - 1)
-                    && self.is_empty(state, x, y - 2)
-                    && !self.is_dead_tile(x, y - 2)
-                {
-                    let mut child = state.clone();
-                    self.set_crate(&mut child, x, y - 2, true);
-                    self.set_crate(&mut child, x, y - 1, false);
-                    child.player = (x, y - 1);
-                    if !self.is_unsolvable(&child) {
-                        children.push((child, read_path(&paths, index, Action::Up)));
+        let mut paths = vec![None; self.tile_count()];
+        let mut seen = vec![false; self.tile_count()];
+        let mut queue = VecDeque::new();
+
+        // walks back from the pull to where the player started, then pushes
+        let read_path = |paths: &Vec<Option<Action>>, tile, push| -> Box<[Action]> {
+            let mut path: Vec<_> = self
+                .read_path(paths, tile)
+                .into_iter()
+                .map(Action::reverse)
+                .collect();
+
+            path.reverse();
+            path.push(push);
+            path.into_boxed_slice()
+        };
+
+        match start {
+            Some(player) => queue.push_back((player, None)),
+            // every tile is its own starting point, so none of them need walking to
+            None => queue.extend((0..self.tile_count()).map(|tile| (tile, None))),
+        }
+
+        while let Some((tile, action)) = queue.pop_front() {
+            if !seen[tile] && !state.crates[tile] {
+                seen[tile] = true;
+                paths[tile] = action;
+
+                // a crate in front of the player is dragged along as they step back,
+                // which undoes a push in the direction the crate is in
+                for push in Action::ALL {
+                    let crate_tile = self.neighbour(tile, push).filter(|c| state.crates[*c]);
+                    let back = self
+                        .neighbour(tile, push.reverse())
+                        .filter(|b| !state.crates[*b]);
+
+                    if let (Some(crate_tile), Some(back)) = (crate_tile, back) {
+                        if !self.pull_dead_tiles[tile] {
+                            let mut parent = state.clone();
+                            parent.crates[crate_tile] = false;
+                            parent.crates[tile] = true;
+                            parent.player = back;
+                            parents.push((parent, read_path(&paths, tile, push)));
+                        }
                     }
                 }
 
-                if self.is_crate(state, x, y + 1)
-                    && self.is_empty(state, x, y + 2)
-                    && !self.is_dead_tile(x, y + 2)
-                {
-                    let mut child = state.clone();
-                    self.set_crate(&mut child, x, y + 2, true);
-                    self.set_crate(&mut child, x, y + 1, false);
-                    child.player = (x, y + 1);
-                    if !self.is_unsolvable(&child) {
-                        children.push((child, read_path(&paths, index, Action::Down)));
+                for step in Action::ALL {
+                    if let Some(next) = self.neighbour(tile, step) {
+                        queue.push_back((next, Some(step)));
                     }
                 }
+            }
+        }
 
-                if self.is_crate(state, x - 1, y)
-                    && self.is_empty(state, x - 2, y)
-                    && !self.is_dead_tile(x - 2, y)
-                {
-                    let mut child = state.clone();
-                    self.set_crate(&mut child, x - 2, y, true);
-                    self.set_crate(&mut child, x - 1, y, false);
-                    child.player = (x - 1, y);
-                    if !self.is_unsolvable(&child) {
-                        children.push((child, read_path(&paths, index, Action::Left)));
-                    }
+        parents
+    }
+
+    /// Moves the player to the top-left-most tile they can walk to, so that states
+    /// differing only by where the player stands in the same region compare equal.
+    pub fn normalize_player(&self, state: &BoardState) -> BoardState {
+        let mut seen = vec![false; self.tile_count()];
+        let mut queue = VecDeque::new();
+        let mut best = usize::MAX;
+
+        queue.push_back(self.player_tile(state));
+
+        while let Some(tile) = queue.pop_front() {
+            if !seen[tile] && !state.crates[tile] {
+                seen[tile] = true;
+                // tiles are numbered in reading order
+                best = std::cmp::min(best, tile);
+
+                for step in Action::ALL {
+                    queue.extend(self.neighbour(tile, step));
                 }
+            }
+        }
 
-                if self.is_crate(state, x + 1, y)
-                    && self.is_empty(state, x + 2, y)
-                    && !self.is_dead_tile(x + 2, y)
-                {
-                    let mut child = state.clone();
-                    self.set_crate(&mut child, x + 2, y, true);
-                    self.set_crate(&mut child, x + 1, y, false);
-                    child.player = (x + 1, y);
-                    if !self.is_unsolvable(&child) {
-                        children.push((child, read_path(&paths, index, Action::Right)));
+        BoardState {
+            player: best,
+            crates: state.crates.clone(),
+        }
+    }
+
+    /// The shortest walk for the player to `to` that doesn't move any crates.
+    pub fn walk(&self, state: &BoardState, to: (u32, u32)) -> Option<Vec<Action>> {
+        let to = self.tile(to)?;
+        let mut paths: Vec<Option<Action>> = vec![None; self.tile_count()];
+        let mut seen = vec![false; self.tile_count()];
+        let mut queue = VecDeque::new();
+
+        queue.push_back((self.player_tile(state), None));
+
+        while let Some((tile, action)) = queue.pop_front() {
+            if !seen[tile] && !state.crates[tile] {
+                seen[tile] = true;
+                paths[tile] = action;
+
+                if tile == to {
+                    let mut path = self.read_path(&paths, tile);
+                    path.reverse();
+                    return Some(path);
+                }
+
+                for step in Action::ALL {
+                    if let Some(next) = self.neighbour(tile, step) {
+                        queue.push_back((next, Some(step)));
                     }
                 }
+            }
+        }
+
+        None
+    }
+
+    /// Every tile the player can walk to without moving a crate or going through
+    /// `others`, each with the moves that reach it listed last move first.
+    pub(crate) fn walks_among(&self, state: &BoardState, others: &[usize]) -> Vec<Walk> {
+        let mut walks = Vec::new();
+        let mut paths: Vec<Option<Action>> = vec![None; self.tile_count()];
+        let mut seen = vec![false; self.tile_count()];
+        let mut queue = VecDeque::new();
+
+        queue.push_back((self.player_tile(state), None));
+
+        while let Some((tile, action)) = queue.pop_front() {
+            if !seen[tile] && !state.crates[tile] && !others.contains(&tile) {
+                seen[tile] = true;
+                paths[tile] = action;
+
+                if action.is_some() {
+                    let path = self.read_path(&paths, tile);
+                    walks.push((tile, path.into_boxed_slice()));
+                }
 
-                queue.push_back((x, y - 1, Some(Action::Up)));
-                queue.push_back((x, y + 1, Some(Action::Down)));
-                queue.push_back((x - 1, y, Some(Action::Left)));
-                queue.push_back((x + 1, y, Some(Action::Right)));
+                for step in Action::ALL {
+                    if let Some(next) = self.neighbour(tile, step) {
+                        queue.push_back((next, Some(step)));
+                    }
+                }
             }
         }
 
-        children
+        walks
+    }
+
+    /// Number of crates in `state` that aren't where a crate is in `target`.
+    pub fn misplaced_crates(&self, state: &BoardState, target: &BoardState) -> u32 {
+        state
+            .crates
+            .iter()
+            .zip(target.crates.iter())
+            .filter(|(here, there)| **here && !**there)
+            .count() as u32
+    }
+
+    /// Applies a single player move to `state`, pushing a crate if there is one in the way.
+    /// Returns the new state and whether a crate was pushed, or `None` if the move is illegal.
+    pub fn step(&self, state: &BoardState, action: Action) -> Option<(BoardState, bool)> {
+        self.step_among(state, &[], action)
+    }
+
+    /// `step` with `others` standing in the way, as other players do in Multiban.
+    pub(crate) fn step_among(
+        &self,
+        state: &BoardState,
+        others: &[usize],
+        action: Action,
+    ) -> Option<(BoardState, bool)> {
+        let is_other = |tile: &usize| others.contains(tile);
+
+        let to = self
+            .neighbour(self.player_tile(state), action)
+            .filter(|to| !is_other(to))?;
+
+        let mut next = state.clone();
+        next.player = to;
+
+        if !state.crates[to] {
+            return Some((next, false));
+        }
+
+        let beyond = self
+            .neighbour(to, action)
+            .filter(|beyond| !state.crates[*beyond] && !is_other(beyond))?;
+
+        next.crates[to] = false;
+        next.crates[beyond] = true;
+        Some((next, true))
+    }
+
+    /// Draws the board in the usual level format, with dead tiles marked as
+    /// `-` (empty), `%` (player) and `!` (crate).
+    pub fn render(&self, state: &BoardState) -> String {
+        self.draw(state, &[state.player], Some(&self.dead_tiles))
+    }
+
+    /// Writes the board out as a level that `parse_level_string` can read back.
+    pub fn level_string(&self, state: &BoardState) -> String {
+        self.draw(state, &[state.player], None)
+            .lines()
+            .map(|line| line.trim_end().to_string() + "\n")
+            .collect()
     }
 
-    pub fn parse_level_string(level: &String) -> Result<(Self, BoardState), &'static str> {
+    /// `render` with every one of `players` drawn, ignoring the state's own player.
+    pub(crate) fn render_players(&self, state: &BoardState, players: &[usize]) -> String {
+        self.draw(state, players, Some(&self.dead_tiles))
+    }
+
+    /// `render` with `dead_tiles` marked in place of the board's own, for rules under
+    /// which different tiles are dead.
+    pub(crate) fn render_dead(&self, state: &BoardState, dead_tiles: &[bool]) -> String {
+        self.draw(state, &[state.player], Some(dead_tiles))
+    }
+
+    fn draw(&self, state: &BoardState, players: &[usize], dead_tiles: Option<&[bool]>) -> String {
+        let mut chars = vec![' '; self.width * self.height];
+
+        for (x, y) in self.walls.iter() {
+            chars[*y as usize * self.width + *x as usize] = '#';
+        }
+
+        for (tile, (x, y)) in self.positions.iter().enumerate() {
+            let is_player = players.contains(&tile);
+            chars[*y as usize * self.width + *x as usize] = match (
+                dead_tiles.is_some_and(|dead| dead[tile]),
+                self.goal_tiles[tile],
+                state.crates[tile],
+                is_player,
+            ) {
+                (true, _, false, false) => '-',
+                (true, _, false, true) => '%',
+                (true, _, true, _) => '!',
+                (false, true, false, false) => '.',
+                (false, true, false, true) => '+',
+                (false, false, true, _) => '$',
+                (false, true, true, _) => '*',
+                (false, false, false, true) => '@',
+                (false, false, false, false) => ' ',
+            };
+        }
+
+        let mut out = String::new();
+        for row in chars.chunks(self.width) {
+            out.extend(row);
+            out.push('\n');
+        }
+
+        out
+    }
+
+    pub fn parse_level_string(level: &str) -> Result<(Self, BoardState), &'static str> {
+        let (board, players, crates) = Board::parse(level, false, 0, &[])?;
+
+        Ok((
+            board,
+            BoardState {
+                player: players[0],
+                crates,
+            },
+        ))
+    }
+
+    /// Reads a level that may have several players, as in Multiban, returning their
+    /// positions alongside the crates.
+    pub(crate) fn parse_multiplayer_level_string(level: &str) -> Result<Multiplayer, &'static str> {
+        Board::parse(level, true, 0, &[])
+    }
+
+    /// Reads a level that has `spare` more crates than goals, for variants where some
+    /// crates can be got rid of. The player's area takes in whatever can be walked to
+    /// from `entrances` too, for variants with other ways of getting about.
+    pub(crate) fn parse_with_spare_crates(
+        level: &str,
+        spare: usize,
+        entrances: &[(u32, u32)],
+    ) -> Result<(Self, BoardState), &'static str> {
+        let (board, players, crates) = Board::parse(level, false, spare, entrances)?;
+
+        Ok((
+            board,
+            BoardState {
+                player: players[0],
+                crates,
+            },
+        ))
+    }
+
+    fn parse(
+        level: &str,
+        multiplayer: bool,
+        spare: usize,
+        entrances: &[(u32, u32)],
+    ) -> Result<Multiplayer, &'static str> {
         // ensure that the level only contains valid characters
         for c in level.chars() {
             if !"#pPbB@+$*. -_\n".contains(c) {
@@ -267,54 +734,58 @@ impl Board {
         let mut players = Vec::new();
 
         let mut goals = Vec::new();
-        let mut walls = vec![false; width * height];
-        let mut crates = vec![false; width * height];
-
-        let mut num_crates = 0;
+        let mut crates = Vec::new();
+        let mut walls = Vec::new();
 
         for (i, line) in lines.into_iter().enumerate() {
             for (j, c) in line.chars().enumerate() {
+                let position = (j as u32, i as u32);
+
                 match c {
-                    '#' => walls[width * i + j] = true,
-                    'p' | '@' => players.push((j as u32, i as u32)),
+                    '#' => walls.push(position),
+                    'p' | '@' => players.push(position),
                     'P' | '+' => {
-                        players.push((j as u32, i as u32));
-                        goals.push((j as u32, i as u32));
-                    }
-                    'b' | '$' => {
-                        crates[width * i + j] = true;
-                        num_crates += 1;
+                        players.push(position);
+                        goals.push(position);
                     }
+                    'b' | '$' => crates.push(position),
                     'B' | '*' => {
-                        goals.push((j as u32, i as u32));
-                        crates[width * i + j] = true;
-                        num_crates += 1;
+                        goals.push(position);
+                        crates.push(position);
                     }
-                    '.' => goals.push((j as u32, i as u32)),
+                    '.' => goals.push(position),
                     ' ' | '-' | '_' => (),
-                    _ => panic!(),
+                    _ => return Err("Level contains invalid character"),
                 }
             }
         }
 
-        if players.len() == 0 {
+        if players.is_empty() {
             return Err("Level has no player");
-        } else if players.len() > 1 {
+        } else if players.len() > 1 && !multiplayer {
             return Err("Level has more than one player");
         }
 
-        if num_crates != goals.len() {
+        if crates.len() != goals.len() + spare {
             return Err("Number of crates and number of goals are not the same");
         }
 
-        // verify the level is enclosed in walls
-        let mut interior = vec![false; walls.len()];
-        let mut queue = VecDeque::new();
+        // the text is only gone over in full here, to find the tiles inside the level
+        let mut is_wall = vec![false; width * height];
+        for (x, y) in walls.iter() {
+            is_wall[*y as usize * width + *x as usize] = true;
+        }
 
-        queue.push_back((players[0].0 as usize, players[0].1 as usize));
+        // verify the level is enclosed in walls
+        let mut interior = vec![false; width * height];
+        let mut queue: VecDeque<_> = players
+            .iter()
+            .chain(entrances)
+            .map(|(x, y)| (*x as usize, *y as usize))
+            .collect();
 
         while let Some((x, y)) = queue.pop_front() {
-            if !interior[y * width + x] && !walls[y * width + x] {
+            if !interior[y * width + x] && !is_wall[y * width + x] {
                 if x == 0 || x == width - 1 || y == 0 || y == height - 1 {
                     return Err("Player is not enclosed in walls");
                 } else {
@@ -327,145 +798,146 @@ impl Board {
             }
         }
 
-        let mut goal_tiles = vec![false; walls.len()];
-        for (x, y) in goals.iter() {
-            goal_tiles[*y as usize * width + *x as usize] = true;
+        // number the tiles inside in reading order
+        let mut tiles = vec![None; width * height];
+        let mut positions = Vec::new();
+        for (i, inside) in interior.iter().enumerate() {
+            if *inside {
+                tiles[i] = Some(positions.len());
+                positions.push(((i % width) as u32, (i / width) as u32));
+            }
         }
 
-        let dead_tiles = Board::find_dead_tiles(width, &walls, &interior, &goal_tiles);
-
-        // print out board info for debug purposes
-        // TODO: separate this out into a function
-        for i in 0..walls.len() {
-            let is_player = i == players[0].1 as usize * width + players[0].0 as usize;
-            match (walls[i], dead_tiles[i], goal_tiles[i], crates[i], is_player) {
-                (true, _, _, _, _) => print!("#"),
-                (false, true, _, false, false) => print!("-"),
-                (false, true, _, false, true) => print!("%"),
-                (false, true, _, true, _) => print!("!"),
-                (false, false, true, false, false) => print!("."),
-                (false, false, true, false, true) => print!("+"),
-                (false, false, false, true, _) => print!("$"),
-                (false, false, true, true, _) => print!("*"),
-                (false, false, false, false, true) => print!("@"),
-                (false, false, false, false, false) => print!(" "),
-            }
+        let tile = |(x, y): (u32, u32)| tiles[y as usize * width + x as usize];
 
-            if i % width == width - 1 {
-                println!();
-            }
+        // anything next to a tile inside is either a wall or inside too
+        let neighbours: Vec<[Option<usize>; 4]> = positions
+            .iter()
+            .map(|position| Action::ALL.map(|action| tile(action.apply(*position))))
+            .collect();
+
+        let crates = crates
+            .iter()
+            .map(|position| tile(*position))
+            .collect::<Option<Vec<_>>>();
+        let goals = goals
+            .iter()
+            .map(|position| tile(*position))
+            .collect::<Option<Vec<_>>>();
+
+        let (crates, goals) = match (crates, goals) {
+            (Some(crates), Some(goals)) => (crates, goals),
+            _ => return Err("Level has crates or goals the player can't reach"),
+        };
+
+        // the player's area was found by walking out from the players, so they're all in it
+        let players: Vec<_> = players
+            .iter()
+            .filter_map(|position| tile(*position))
+            .collect();
+
+        let mut crate_tiles = vec![false; positions.len()];
+        for crate_tile in crates {
+            crate_tiles[crate_tile] = true;
         }
 
-        let goal_distances = Board::calculate_goal_distances(&goals, width, &walls, &dead_tiles);
+        let mut goal_tiles = vec![false; positions.len()];
+        for goal in goals.iter() {
+            goal_tiles[*goal] = true;
+        }
+
+        let dead_tiles = Board::find_dead_tiles(&neighbours, &goal_tiles);
+        let pull_dead_tiles = Board::find_pull_dead_tiles(&neighbours, &goal_tiles);
+
+        let goal_distances = Board::calculate_goal_distances(&goals, &neighbours, &dead_tiles);
 
         Ok((
             Board {
+                neighbours: neighbours.into_boxed_slice(),
+                positions: positions.into_boxed_slice(),
                 goals: goals.into_iter().zip(goal_distances).collect(),
                 goal_tiles: goal_tiles.into_boxed_slice(),
-                walls: walls.into_boxed_slice(),
                 dead_tiles: dead_tiles.into_boxed_slice(),
-                width: width,
-            },
-            BoardState {
-                player: players[0],
-                crates: crates.into_boxed_slice(),
+                pull_dead_tiles: pull_dead_tiles.into_boxed_slice(),
+                walls: walls.into_boxed_slice(),
+                width,
+                height,
             },
+            players,
+            crate_tiles.into_boxed_slice(),
         ))
     }
 
-    fn calculate_goal_distances<'a>(
-        goals: &'a [(u32, u32)],
-        width: usize,
-        walls: &'a [bool],
-        dead_tiles: &'a [bool],
+    fn calculate_goal_distances(
+        goals: &[usize],
+        neighbours: &[[Option<usize>; 4]],
+        dead_tiles: &[bool],
     ) -> Vec<Box<[u32]>> {
         goals
             .iter()
             .map(|goal| {
-                Board::calculate_goal_distance(*goal, width, walls, dead_tiles).into_boxed_slice()
+                Board::calculate_goal_distance(*goal, neighbours, dead_tiles).into_boxed_slice()
             })
             .collect()
     }
 
     fn calculate_goal_distance(
-        goal: (u32, u32),
-        width: usize,
-        walls: &[bool],
+        goal: usize,
+        neighbours: &[[Option<usize>; 4]],
         dead_tiles: &[bool],
     ) -> Vec<u32> {
-        let mut dists = vec![0; walls.len()];
+        let mut dists = vec![0; neighbours.len()];
 
-        let mut seen = vec![false; walls.len()];
+        let mut seen = vec![false; neighbours.len()];
         let mut queue = VecDeque::new();
 
-        queue.push_back((goal.0 as usize, goal.1 as usize, 0));
+        queue.push_back((goal, 0));
 
-        while let Some((x, y, d)) = queue.pop_front() {
-            if !seen[y * width + x] && !walls[y * width + x] && !dead_tiles[y * width + x] {
-                seen[y * width + x] = true;
-                dists[y * width + x] = d;
-                queue.push_back((x + 1, y, d + 1));
-                queue.push_back((x - 1, y, d + 1));
-                queue.push_back((x, y + 1, d + 1));
-                queue.push_back((x, y - 1, d + 1));
+        while let Some((tile, d)) = queue.pop_front() {
+            if !seen[tile] && !dead_tiles[tile] {
+                seen[tile] = true;
+                dists[tile] = d;
+                queue.extend(neighbours[tile].iter().flatten().map(|next| (*next, d + 1)));
             }
         }
 
         dists
     }
 
-    fn find_dead_tiles(
-        width: usize,
-        walls: &[bool],
-        interior: &[bool],
-        goal_tiles: &[bool],
-    ) -> Vec<bool> {
-        let mut corners = vec![false; walls.len()];
-        let mut next_to_walls = vec![false; walls.len()];
+    fn find_dead_tiles(neighbours: &[[Option<usize>; 4]], goal_tiles: &[bool]) -> Vec<bool> {
+        let is_wall = |tile: usize, action: Action| neighbours[tile][action as usize].is_none();
 
         // find corners and open tiles next to walls
-        for (i, inside) in interior.iter().enumerate() {
-            if *inside {
-                if walls[i - width] && walls[i + 1]
-                    || walls[i + 1] && walls[i + width]
-                    || walls[i + width] && walls[i - 1]
-                    || walls[i - 1] && walls[i - width]
-                {
-                    corners[i] = true;
-                }
-
-                if walls[i - 1] || walls[i + 1] || walls[i - width] || walls[i + width] {
-                    next_to_walls[i] = true;
-                }
-            }
-        }
+        let corners: Vec<bool> = (0..neighbours.len())
+            .map(|i| {
+                (is_wall(i, Action::Left) || is_wall(i, Action::Right))
+                    && (is_wall(i, Action::Up) || is_wall(i, Action::Down))
+            })
+            .collect();
+        let next_to_walls: Vec<bool> = neighbours
+            .iter()
+            .map(|next| next.iter().any(Option::is_none))
+            .collect();
 
-        let corners = corners;
-        let next_to_walls = next_to_walls;
+        // whether the run of tiles along a wall from a corner in direction `along`
+        // ends in another corner, with no goal on it
+        let is_dead_along = |start: usize, along: Action| -> bool {
+            let mut tile = Some(start);
 
-        let is_dead_across = |start: usize| -> bool {
-            for i in start.. {
+            while let Some(i) = tile {
                 if !next_to_walls[i] || goal_tiles[i] {
                     return false;
-                } else if corners[i] && walls[i + 1] {
+                } else if corners[i] && is_wall(i, along) {
                     return true;
                 }
-            }
-            unreachable!()
-        };
 
-        let is_dead_down = |start: usize| -> bool {
-            for i in (start..).step_by(width) {
-                if !next_to_walls[i] || goal_tiles[i] {
-                    return false;
-                } else if corners[i] && walls[i + width] {
-                    return true;
-                }
+                tile = neighbours[i][along as usize];
             }
-            unreachable!()
+
+            false
         };
 
-        let mut dead_tiles = vec![false; walls.len()];
+        let mut dead_tiles = vec![false; neighbours.len()];
 
         // use corners and next to walls to find dead tiles
         for (i, corner) in corners.iter().enumerate() {
@@ -473,26 +945,51 @@ impl Board {
                 // all corners not on goals are dead tiles
                 dead_tiles[i] = true;
 
-                // search across
-                if is_dead_across(i) {
-                    let mut j = i;
-                    while !walls[j] {
-                        dead_tiles[j] = true;
-                        j += 1;
+                // search across and down
+                for along in [Action::Right, Action::Down] {
+                    if is_dead_along(i, along) {
+                        let mut tile = Some(i);
+                        while let Some(j) = tile {
+                            dead_tiles[j] = true;
+                            tile = neighbours[j][along as usize];
+                        }
                     }
                 }
+            }
+        }
+
+        dead_tiles
+    }
+
+    // Dead tiles for pulling are different from those for pushing: a crate can be pulled
+    // out of a corner, but pulling it onto a tile it could never be pulled to from a goal
+    // means it could never be pushed back onto one either. Pulls start from the goals rather
+    // than the level's crates, so they're the same for every state of the level.
+    fn find_pull_dead_tiles(neighbours: &[[Option<usize>; 4]], goal_tiles: &[bool]) -> Vec<bool> {
+        let mut reachable = vec![false; neighbours.len()];
+        let mut queue: VecDeque<usize> = goal_tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| **g)
+            .map(|(i, _)| i)
+            .collect();
+
+        while let Some(i) = queue.pop_front() {
+            if !reachable[i] {
+                reachable[i] = true;
 
-                // search down
-                if is_dead_down(i) {
-                    let mut j = i;
-                    while !walls[j] {
-                        dead_tiles[j] = true;
-                        j += width;
+                // the player stands on the tile the crate is pulled to, then steps on past it
+                for pull in Action::ALL {
+                    let ahead = neighbours[i][pull as usize];
+                    let beyond = ahead.and_then(|ahead| neighbours[ahead][pull as usize]);
+
+                    if let (Some(ahead), Some(_)) = (ahead, beyond) {
+                        queue.push_back(ahead);
                     }
                 }
             }
         }
 
-        dead_tiles
+        reachable.into_iter().map(|reachable| !reachable).collect()
     }
 }
@@ -0,0 +1,259 @@
+use crate::board::{Action, Board, BoardState};
+use crate::search::{self, Limit, ProgressTracker, SearchConfig};
+
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+const HEADER: &str = "pushcrate unsolvability certificate";
+
+/// Evidence that a level can't be solved: a set of states holding the start and no
+/// goal, which no push leads out of. Pushes of a crate onto one of the dead tiles are
+/// the only exception, as a crate there could never reach a goal even on its own.
+/// Each state has the player on the top-left-most tile they can walk to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    pub dead_tiles: Vec<(u32, u32)>,
+    pub states: Vec<BoardState>,
+}
+
+pub enum Proof {
+    Unsolvable(Certificate),
+    /// A goal can be reached, so there's nothing to prove.
+    Solvable,
+    LimitReached(Limit),
+}
+
+impl Certificate {
+    /// Writes the certificate out with one line for the dead tiles and one per state,
+    /// listing the player's position and then each crate's.
+    pub fn to_text(&self, board: &Board) -> String {
+        let position = |(x, y): (u32, u32)| format!(" {},{}", x, y);
+
+        let mut text = format!("{}\ndead", HEADER);
+        self.dead_tiles
+            .iter()
+            .for_each(|tile| text.push_str(&position(*tile)));
+        text.push('\n');
+
+        for state in &self.states {
+            text.push_str("state");
+            text.push_str(&position(board.player_position(state)));
+            board
+                .iter_crates(state)
+                .for_each(|tile| text.push_str(&position(tile)));
+            text.push('\n');
+        }
+
+        text
+    }
+
+    pub fn parse(board: &Board, text: &str) -> Result<Certificate, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("Not an unsolvability certificate".to_string());
+        }
+
+        let mut dead_tiles = None;
+        let mut states = Vec::new();
+
+        for (i, line) in lines.enumerate().filter(|(_, line)| !line.is_empty()) {
+            let line_error = |message: &str| format!("Line {}: {}", i + 2, message);
+            let mut fields = line.split_whitespace();
+            let kind = fields.next();
+
+            let positions = fields
+                .map(|field| {
+                    let (x, y) = field.split_once(',')?;
+                    let position = (x.parse().ok()?, y.parse().ok()?);
+                    board.tile(position).map(|_| position)
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| line_error("expected positions on the board as x,y"))?;
+
+            match (kind, positions.split_first()) {
+                (Some("dead"), _) if dead_tiles.is_none() => dead_tiles = Some(positions),
+                (Some("state"), Some((player, crates))) => {
+                    let player = board.tile(*player).unwrap();
+                    let mut state = BoardState::new(player, vec![false; board.tile_count()].into());
+                    for tile in crates {
+                        board.set_crate_at(&mut state, board.tile(*tile).unwrap(), true);
+                    }
+                    states.push(state);
+                }
+                _ => return Err(line_error("expected one dead line, then state lines")),
+            }
+        }
+
+        Ok(Certificate {
+            dead_tiles: dead_tiles.ok_or("Certificate has no dead line")?,
+            states,
+        })
+    }
+}
+
+/// Explores every state reachable from `start` to prove it can't be solved. Only the
+/// rules and lone crates stuck on dead tiles are used to cut the search short, so that
+/// the result can be checked by `check` without trusting the solver's deadlock rules.
+pub fn prove_unsolvable(board: &Board, start: &BoardState, config: &SearchConfig) -> Proof {
+    let state_bytes = 2 * size_of::<usize>() + start.size_bytes();
+    let start_time = Instant::now();
+    let mut tracker = ProgressTracker::create(config, Default::default());
+
+    let start = board.normalize_player(start);
+    let mut dead = HashMap::new();
+    let mut seen = HashSet::new();
+    seen.insert(start.clone());
+    let mut states = vec![start];
+
+    let mut next = 0;
+    let proof = loop {
+        let state = match states.get(next) {
+            Some(state) => state.clone(),
+            None => {
+                let mut dead_tiles: Vec<_> = dead
+                    .into_iter()
+                    .filter(|(_, is_dead)| *is_dead)
+                    .map(|(tile, _)| tile)
+                    .collect();
+                dead_tiles.sort_by_key(|(x, y)| (*y, *x));
+                break Proof::Unsolvable(Certificate { dead_tiles, states });
+            }
+        };
+        next += 1;
+
+        let memory = (seen.len() + states.len()) * state_bytes;
+        tracker.update(0, 0, states.len() - next, memory);
+
+        if board.is_goal_state(&state) {
+            break Proof::Solvable;
+        }
+        if let Some(limit) = search::check_limits(config, &tracker.stats, start_time) {
+            break Proof::LimitReached(limit);
+        }
+
+        for (child, pushed_to) in pushes(board, &state) {
+            if *dead
+                .entry(pushed_to)
+                .or_insert_with(|| is_dead(board, pushed_to))
+            {
+                tracker.stats.prunes.dead_tile += 1;
+                continue;
+            }
+
+            tracker.stats.nodes_generated += 1;
+            let child = board.normalize_player(&child);
+            if seen.insert(child.clone()) {
+                states.push(child);
+            } else {
+                tracker.stats.duplicates += 1;
+            }
+        }
+    };
+
+    tracker.finish();
+    proof
+}
+
+/// Checks that `certificate` proves the level can't be solved from `start`, or says
+/// what's wrong with it.
+pub fn check(board: &Board, start: &BoardState, certificate: &Certificate) -> Result<(), String> {
+    for (x, y) in &certificate.dead_tiles {
+        if !is_dead(board, (*x, *y)) {
+            return Err(format!("A crate on {},{} can still reach a goal", x, y));
+        }
+    }
+
+    let states: HashSet<&BoardState> = certificate.states.iter().collect();
+
+    if !states.contains(&board.normalize_player(start)) {
+        return Err("The start isn't in the certificate".to_string());
+    }
+
+    for (i, state) in certificate.states.iter().enumerate() {
+        if board.normalize_player(state) != *state {
+            return Err(format!(
+                "State {} doesn't have the player top-left-most",
+                i + 1
+            ));
+        }
+        if board.is_goal_state(state) {
+            return Err(format!("State {} is solved", i + 1));
+        }
+
+        for (child, pushed_to) in pushes(board, state) {
+            if !certificate.dead_tiles.contains(&pushed_to)
+                && !states.contains(&board.normalize_player(&child))
+            {
+                return Err(format!(
+                    "State {} has a push to a state that isn't in the certificate",
+                    i + 1
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Every state a single push away from `state`, with where the crate was pushed to.
+/// The player is walked around with `Board::step` alone, so a check relies on
+/// nothing but the rules.
+fn pushes(board: &Board, state: &BoardState) -> Vec<(BoardState, (u32, u32))> {
+    let mut children = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = vec![state.clone()];
+
+    while let Some(walked) = queue.pop() {
+        if !seen.insert(board.player_tile(&walked)) {
+            continue;
+        }
+
+        for action in Action::ALL {
+            match board.step(&walked, action) {
+                Some((child, true)) => {
+                    let pushed_to = action.apply(board.player_position(&child));
+                    children.push((child, pushed_to));
+                }
+                Some((next, false)) => queue.push(next),
+                None => (),
+            }
+        }
+    }
+
+    children
+}
+
+/// Whether a crate on `tile` could never be pushed onto a goal, even with no other
+/// crates in the way and the player starting anywhere.
+fn is_dead(board: &Board, tile: (u32, u32)) -> bool {
+    let crate_tile = match board.tile(tile) {
+        Some(crate_tile) => crate_tile,
+        None => return false,
+    };
+    let mut crates = vec![false; board.tile_count()];
+    crates[crate_tile] = true;
+
+    let mut seen = HashSet::new();
+    let mut queue: Vec<_> = (0..board.tile_count())
+        .filter(|player| *player != crate_tile)
+        .map(|player| BoardState::new(player, crates.clone().into()))
+        .collect();
+
+    while let Some(state) = queue.pop() {
+        let state = board.normalize_player(&state);
+        if board.iter_crates(&state).any(|at| is_goal(board, at)) {
+            return false;
+        }
+        if seen.insert(state.clone()) {
+            queue.extend(pushes(board, &state).into_iter().map(|(child, _)| child));
+        }
+    }
+
+    true
+}
+
+fn is_goal(board: &Board, position: (u32, u32)) -> bool {
+    board
+        .tile(position)
+        .is_some_and(|tile| board.is_goal_tile(tile))
+}
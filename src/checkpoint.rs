@@ -0,0 +1,166 @@
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result};
+
+/// A value that can be written into a checkpoint file and read back out of one.
+/// Numbers are written as variable-length integers, so small ones take a byte.
+pub trait Persist: Sized {
+    fn write(&self, out: &mut Vec<u8>);
+
+    /// Reads a value from the front of `input`, moving past it.
+    fn read(input: &mut &[u8]) -> Result<Self>;
+}
+
+pub(crate) fn invalid(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+impl Persist for u64 {
+    fn write(&self, out: &mut Vec<u8>) {
+        let mut value = *self;
+
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let (byte, rest) = input
+                .split_first()
+                .ok_or_else(|| invalid("Checkpoint ends early"))?;
+            *input = rest;
+
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(invalid("Checkpoint has a number that's too long"))
+    }
+}
+
+impl Persist for u32 {
+    fn write(&self, out: &mut Vec<u8>) {
+        u64::from(*self).write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        u32::try_from(u64::read(input)?).map_err(|_| invalid("Checkpoint number out of range"))
+    }
+}
+
+impl Persist for usize {
+    fn write(&self, out: &mut Vec<u8>) {
+        (*self as u64).write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        usize::try_from(u64::read(input)?).map_err(|_| invalid("Checkpoint number out of range"))
+    }
+}
+
+impl Persist for u8 {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        let (byte, rest) = input
+            .split_first()
+            .ok_or_else(|| invalid("Checkpoint ends early"))?;
+        *input = rest;
+        Ok(*byte)
+    }
+}
+
+impl Persist for bool {
+    fn write(&self, out: &mut Vec<u8>) {
+        (*self as u8).write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        match u8::read(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid("Checkpoint has an invalid flag")),
+        }
+    }
+}
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.0.write(out);
+        self.1.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        Ok((A::read(input)?, B::read(input)?))
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.is_some().write(out);
+        if let Some(value) = self {
+            value.write(out);
+        }
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        if bool::read(input)? {
+            Ok(Some(T::read(input)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: Persist> Persist for Box<[T]> {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.len().write(out);
+        self.iter().for_each(|value| value.write(out));
+    }
+
+    fn read(input: &mut &[u8]) -> Result<Self> {
+        let len = usize::read(input)?;
+        // every value takes at least a byte, which keeps a corrupt length from allocating wildly
+        if len > input.len() {
+            return Err(invalid("Checkpoint ends early"));
+        }
+
+        (0..len).map(|_| T::read(input)).collect()
+    }
+}
+
+/// Writes `bits` packed eight to a byte, which is how crate layouts are stored.
+pub(crate) fn write_bits(bits: &[bool], out: &mut Vec<u8>) {
+    bits.len().write(out);
+    for chunk in bits.chunks(8) {
+        out.push(
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, bit)| byte | (*bit as u8) << i),
+        );
+    }
+}
+
+pub(crate) fn read_bits(input: &mut &[u8]) -> Result<Box<[bool]>> {
+    let len = usize::read(input)?;
+    let bytes = len.div_ceil(8);
+    if bytes > input.len() {
+        return Err(invalid("Checkpoint ends early"));
+    }
+
+    let (packed, rest) = input.split_at(bytes);
+    *input = rest;
+
+    Ok((0..len)
+        .map(|i| packed[i / 8] & (1 << (i % 8)) != 0)
+        .collect())
+}
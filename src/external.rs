@@ -0,0 +1,519 @@
+use crate::board::Prunes;
+use crate::checkpoint::Persist;
+use crate::search::{self, Outcome, Partial, ProgressTracker, Puzzle, SearchConfig, SearchResult};
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::time::Instant;
+
+/// Where a disk-backed search keeps its states, and how much of them it may hold
+/// in memory at once.
+#[derive(Clone, Debug)]
+pub struct ExternalConfig {
+    /// Directory the search files go in. A subdirectory is made for them, and
+    /// removed again when the search finishes.
+    pub dir: PathBuf,
+    /// Bytes of states to sort in memory at once, and to buffer before writing out.
+    pub buffer: usize,
+}
+
+impl Default for ExternalConfig {
+    fn default() -> Self {
+        ExternalConfig {
+            dir: std::env::temp_dir(),
+            buffer: 256 << 20,
+        }
+    }
+}
+
+/// A bucket holds every state reached at the same cost `g` with the same heuristic
+/// `h`. As the heuristic depends only on the state, a state can only ever turn up
+/// again in a bucket with the same `h`.
+type Bucket = (u32, u32);
+
+/// A state as it's stored on disk: its encoding, which sorting and duplicate removal
+/// compare, and the bucket of the state it was reached from.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Record {
+    state: Box<[u8]>,
+    parent: Option<Bucket>,
+}
+
+impl Record {
+    fn size(&self) -> usize {
+        size_of::<Record>() + self.state.len()
+    }
+
+    /// Appends the record with its length in front, so it can be read back from a stream.
+    fn write(&self, out: &mut Vec<u8>) {
+        let mut body = Vec::new();
+        self.parent.write(&mut body);
+        body.extend_from_slice(&self.state);
+
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend(body);
+    }
+}
+
+/// Reads back the records in a bucket or run file, in the order they were written.
+struct Records {
+    reader: BufReader<File>,
+}
+
+impl Records {
+    fn open(path: &Path) -> io::Result<Self> {
+        Ok(Records {
+            reader: BufReader::new(File::open(path)?),
+        })
+    }
+
+    fn next(&mut self) -> io::Result<Option<Record>> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let mut body = vec![0; u32::from_le_bytes(len) as usize];
+        self.reader.read_exact(&mut body)?;
+
+        let mut input = body.as_slice();
+        let parent = Option::read(&mut input)?;
+        Ok(Some(Record {
+            state: input.into(),
+            parent,
+        }))
+    }
+}
+
+/// The search's own directory, which is removed however the search ends.
+struct Workspace(PathBuf);
+
+impl Workspace {
+    fn create(dir: &Path) -> io::Result<Self> {
+        // several searches can run in one process, as they do in `bench`
+        static SEARCHES: AtomicUsize = AtomicUsize::new(0);
+        let path = dir.join(format!(
+            "pushcrate-external-{}-{}",
+            std::process::id(),
+            SEARCHES.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        fs::create_dir_all(&path)?;
+        Ok(Workspace(path))
+    }
+
+    fn file(&self, name: String) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Buckets waiting to be expanded. Children are buffered in memory and appended to
+/// their bucket's file when the buffer fills up, which keeps few files open at once.
+struct Open {
+    buckets: BTreeSet<(u32, u32, u32)>,
+    pending: HashMap<Bucket, Vec<u8>>,
+    pending_bytes: usize,
+    /// Records written to buckets that haven't been read back yet.
+    waiting: usize,
+}
+
+impl Open {
+    fn add(&mut self, (g, h): Bucket, record: &Record) {
+        self.buckets.insert((g + h, g, h));
+
+        let pending = self.pending.entry((g, h)).or_default();
+        let before = pending.len();
+        record.write(pending);
+        self.pending_bytes += pending.len() - before;
+        self.waiting += 1;
+    }
+
+    fn flush(&mut self, workspace: &Workspace, bucket: Bucket) -> io::Result<()> {
+        if let Some(pending) = self.pending.remove(&bucket) {
+            self.pending_bytes -= pending.len();
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(open_file(workspace, bucket))?
+                .write_all(&pending)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush_all(&mut self, workspace: &Workspace) -> io::Result<()> {
+        let buckets: Vec<Bucket> = self.pending.keys().copied().collect();
+        buckets
+            .into_iter()
+            .try_for_each(|bucket| self.flush(workspace, bucket))
+    }
+}
+
+fn open_file(workspace: &Workspace, (g, h): Bucket) -> PathBuf {
+    workspace.file(format!("open-{}-{}", g, h))
+}
+
+/// Finds the cheapest path from `start` to a goal like `search::find_path`, but with
+/// the open and closed lists kept on disk rather than in memory. States are grouped
+/// into buckets by cost and heuristic and expanded a bucket at a time in order of
+/// f-value. Each bucket is sorted in chunks of `external.buffer` bytes and merged,
+/// removing duplicates within it and any states already expanded from earlier buckets.
+///
+/// Only the states are stored, not the paths to them, so the path is worked out
+/// afterwards by searching each parent's bucket for the state that leads on to it.
+pub fn find_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+    external: &ExternalConfig,
+) -> io::Result<SearchResult<P::Move>> {
+    let workspace = Workspace::create(&external.dir)?;
+
+    let mut open = Open {
+        buckets: BTreeSet::new(),
+        pending: HashMap::new(),
+        pending_bytes: 0,
+        waiting: 0,
+    };
+    let mut closed: HashMap<Bucket, Vec<PathBuf>> = HashMap::new();
+    let mut files = 0;
+
+    let mut encoded = Vec::new();
+    start.write(&mut encoded);
+    open.add(
+        (0, puzzle.heuristic(start)),
+        &Record {
+            state: encoded.into(),
+            parent: None,
+        },
+    );
+
+    // lowest (h, g) state expanded, reported as the partial solution if a limit is hit
+    let mut best: Option<(u32, u32, P::State, Option<Bucket>)> = None;
+
+    let start_time = Instant::now();
+    let mut tracker = ProgressTracker::create(config, Default::default());
+
+    while let Some((_, g, h)) = open.buckets.pop_first() {
+        open.flush(&workspace, (g, h))?;
+
+        // new children for this bucket go to a fresh file rather than the one being read
+        files += 1;
+        let input = workspace.file(format!("input-{}", files));
+        fs::rename(open_file(&workspace, (g, h)), &input)?;
+
+        let sorted = sort_runs(&workspace, &input, external.buffer, &mut files)?;
+        fs::remove_file(&input)?;
+        open.waiting -= sorted.records;
+        tracker.stats.duplicates += sorted.duplicates;
+        let runs = sorted.runs;
+
+        files += 1;
+        let output = workspace.file(format!("closed-{}-{}-{}", g, h, files));
+        let mut closed_writer = BufWriter::new(File::create(&output)?);
+        let mut earlier = closed
+            .iter()
+            .filter(|((_, closed_h), _)| *closed_h == h)
+            .flat_map(|(_, paths)| paths)
+            .map(|path| Peekable::open(path))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut merge = Merge::open(&runs)?;
+        while let Some(record) = merge.next()? {
+            tracker.stats.duplicates += std::mem::take(&mut merge.duplicates);
+
+            let mut seen = false;
+            for file in earlier.iter_mut() {
+                seen |= file.contains(&record.state)?;
+            }
+            if seen {
+                tracker.stats.duplicates += 1;
+                continue;
+            }
+
+            let mut buffer = Vec::new();
+            record.write(&mut buffer);
+            closed_writer.write_all(&buffer)?;
+
+            let state = P::State::read(&mut &record.state[..])?;
+            let memory = sorted.largest + open.pending_bytes;
+            tracker.update(g, h, open.waiting, memory);
+
+            if puzzle.is_goal_state(&state) {
+                tracker.stats.solution_cost = Some(g);
+                closed_writer.flush()?;
+                closed.entry((g, h)).or_default().push(output);
+                let path = read_path(puzzle, config, &closed, state, g, record.parent)?;
+                return Ok(SearchResult {
+                    outcome: Outcome::Solved(path),
+                    stats: tracker.finish(),
+                });
+            }
+
+            if best
+                .as_ref()
+                .is_none_or(|(best_h, best_g, _, _)| (h, g) < (*best_h, *best_g))
+            {
+                best = Some((h, g, state.clone(), record.parent));
+            }
+
+            if let Some(limit) = search::check_limits(config, &tracker.stats, start_time) {
+                closed_writer.flush()?;
+                closed.entry((g, h)).or_default().push(output);
+                let (heuristic, best_g, state, parent) = best.unwrap();
+                let path = read_path(puzzle, config, &closed, state, best_g, parent)?;
+                return Ok(SearchResult {
+                    outcome: Outcome::LimitReached(limit, Partial { path, heuristic }),
+                    stats: tracker.finish(),
+                });
+            }
+
+            let children = puzzle.create_children_counted(&state, &mut tracker.stats.prunes);
+            tracker.stats.nodes_generated += children.len() as u64;
+
+            for (child, actions) in children {
+                let mut encoded = Vec::new();
+                child.write(&mut encoded);
+                let bucket = (
+                    g + puzzle.cost(&actions, config.metric),
+                    puzzle.heuristic(&child),
+                );
+                open.add(
+                    bucket,
+                    &Record {
+                        state: encoded.into(),
+                        parent: Some((g, h)),
+                    },
+                );
+            }
+
+            if open.pending_bytes > external.buffer {
+                open.flush_all(&workspace)?;
+            }
+        }
+
+        closed_writer.flush()?;
+        closed.entry((g, h)).or_default().push(output);
+        runs.iter().try_for_each(fs::remove_file)?;
+    }
+
+    Ok(SearchResult {
+        outcome: Outcome::Unsolvable,
+        stats: tracker.finish(),
+    })
+}
+
+/// A bucket's file split into sorted runs.
+struct Sorted {
+    runs: Vec<PathBuf>,
+    /// Size of the largest run sorted, in bytes.
+    largest: usize,
+    /// Records read from the file.
+    records: usize,
+    /// Records removed for repeating a state earlier in the same run.
+    duplicates: u64,
+}
+
+/// Splits `input` into sorted runs of at most `buffer` bytes each, with duplicates
+/// within a run already removed.
+fn sort_runs(
+    workspace: &Workspace,
+    input: &Path,
+    buffer: usize,
+    files: &mut usize,
+) -> io::Result<Sorted> {
+    let mut records = Records::open(input)?;
+    let mut sorted = Sorted {
+        runs: Vec::new(),
+        largest: 0,
+        records: 0,
+        duplicates: 0,
+    };
+    let mut done = false;
+
+    while !done {
+        let mut chunk = Vec::new();
+        let mut bytes = 0;
+
+        // a run always takes at least one record, however small the buffer
+        while bytes < buffer || chunk.is_empty() {
+            match records.next()? {
+                Some(record) => {
+                    bytes += record.size();
+                    chunk.push(record);
+                }
+                None => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+
+        if chunk.is_empty() {
+            break;
+        }
+        sorted.largest = sorted.largest.max(bytes);
+        sorted.records += chunk.len();
+
+        // ties keep the first parent written, though any would do
+        chunk.sort_by(|a, b| a.state.cmp(&b.state));
+        let before = chunk.len();
+        chunk.dedup_by(|a, b| a.state == b.state);
+        sorted.duplicates += (before - chunk.len()) as u64;
+
+        *files += 1;
+        let run = workspace.file(format!("run-{}", files));
+        let mut out = Vec::new();
+        chunk.iter().for_each(|record| record.write(&mut out));
+        fs::write(&run, out)?;
+        sorted.runs.push(run);
+    }
+
+    Ok(sorted)
+}
+
+/// Merges sorted runs into one sorted stream with each state once.
+struct Merge {
+    runs: Vec<Records>,
+    heap: BinaryHeap<Reverse<(Record, usize)>>,
+    last: Option<Box<[u8]>>,
+    /// Records skipped for repeating a state from another run.
+    duplicates: u64,
+}
+
+impl Merge {
+    fn open(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut merge = Merge {
+            runs: Vec::new(),
+            heap: BinaryHeap::new(),
+            last: None,
+            duplicates: 0,
+        };
+
+        for path in paths {
+            merge.runs.push(Records::open(path)?);
+            merge.refill(merge.runs.len() - 1)?;
+        }
+
+        Ok(merge)
+    }
+
+    fn refill(&mut self, run: usize) -> io::Result<()> {
+        if let Some(record) = self.runs[run].next()? {
+            self.heap.push(Reverse((record, run)));
+        }
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<Record>> {
+        while let Some(Reverse((record, run))) = self.heap.pop() {
+            self.refill(run)?;
+
+            if self.last.as_ref() != Some(&record.state) {
+                self.last = Some(record.state.clone());
+                return Ok(Some(record));
+            }
+            self.duplicates += 1;
+        }
+
+        Ok(None)
+    }
+}
+
+/// A sorted closed file read alongside the merge, to check states against.
+struct Peekable {
+    records: Records,
+    next: Option<Record>,
+}
+
+impl Peekable {
+    fn open(path: &Path) -> io::Result<Self> {
+        let mut records = Records::open(path)?;
+        let next = records.next()?;
+        Ok(Peekable { records, next })
+    }
+
+    /// Whether the file holds `state`. Must be asked about states in sorted order.
+    fn contains(&mut self, state: &[u8]) -> io::Result<bool> {
+        while let Some(record) = &self.next {
+            if &*record.state >= state {
+                return Ok(&*record.state == state);
+            }
+            self.next = self.records.next()?;
+        }
+
+        Ok(false)
+    }
+}
+
+/// Works back from `state`, reached at cost `g` from the bucket `parent`, to the
+/// start. Each step expands the states in the parent's bucket until one of them
+/// leads to the state at the right cost.
+fn read_path<P: Puzzle>(
+    puzzle: &P,
+    config: &SearchConfig,
+    closed: &HashMap<Bucket, Vec<PathBuf>>,
+    mut state: P::State,
+    mut g: u32,
+    mut parent: Option<Bucket>,
+) -> io::Result<Vec<P::Move>> {
+    // built last move first, the same as each child's moves
+    let mut path = Vec::new();
+
+    while let Some(bucket) = parent {
+        let (previous, moves, grandparent) =
+            find_parent(puzzle, config, &closed[&bucket], &state, g, bucket.0)?
+                .ok_or_else(|| io::Error::other("External search lost the parent of a state"))?;
+
+        path.extend_from_slice(&moves);
+        state = previous;
+        g = bucket.0;
+        parent = grandparent;
+    }
+
+    path.reverse();
+    Ok(path)
+}
+
+type Parent<S, M> = (S, Box<[M]>, Option<Bucket>);
+
+fn find_parent<P: Puzzle>(
+    puzzle: &P,
+    config: &SearchConfig,
+    files: &[PathBuf],
+    state: &P::State,
+    g: u32,
+    parent_g: u32,
+) -> io::Result<Option<Parent<P::State, P::Move>>> {
+    for file in files {
+        let mut records = Records::open(file)?;
+
+        while let Some(record) = records.next()? {
+            let candidate = P::State::read(&mut &record.state[..])?;
+
+            let found = puzzle
+                .create_children_counted(&candidate, &mut Prunes::default())
+                .into_iter()
+                .find(|(child, actions)| {
+                    child == state && parent_g + puzzle.cost(actions, config.metric) == g
+                });
+
+            if let Some((_, moves)) = found {
+                return Ok(Some((candidate, moves, record.parent)));
+            }
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,358 @@
+use crate::search::{
+    self, Limit, Outcome, Partial, ProgressTracker, Puzzle, SearchConfig, SearchResult,
+};
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::io;
+use std::rc::Rc;
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct FrontierConfig {
+    /// How many of the most recently expanded layers are kept to catch duplicates to
+    /// begin with. States from older layers reached again are just expanded a second
+    /// time, but a pass that had to let layers go is no proof that a level can't be
+    /// solved, so the count is doubled after each one until a pass keeps them all.
+    pub layers: usize,
+}
+
+impl Default for FrontierConfig {
+    fn default() -> Self {
+        FrontierConfig { layers: 2 }
+    }
+}
+
+/// A state partway along the path to a node, with the cost of reaching it, which
+/// splits the path into two shorter searches when it's rebuilt.
+type Relay<S> = (Rc<S>, u32);
+
+/// States waiting to be expanded at the same cost, in the order they were reached.
+struct Layer<S> {
+    states: Vec<(Rc<S>, Option<Relay<S>>)>,
+    index: HashSet<Rc<S>>,
+}
+
+impl<S: Eq + Hash> Layer<S> {
+    fn new() -> Self {
+        Layer {
+            states: Vec::new(),
+            index: HashSet::new(),
+        }
+    }
+}
+
+enum Layered<S> {
+    /// The target, the cost it was reached at and the relay on the way there, if the
+    /// path crossed the middle cost.
+    Found(Rc<S>, u32, Option<Relay<S>>),
+    /// Nothing found within the bound. Holds the lowest f-value over it, if anything
+    /// was cut off, and whether every layer expanded was kept to catch duplicates.
+    Exhausted(Option<u32>, bool),
+    LimitReached(Limit),
+}
+
+/// Why a path couldn't be rebuilt.
+enum Lost {
+    /// A search limit was reached partway through.
+    Limit,
+    /// A state on a path already found couldn't be reached again, which is a bug.
+    Unreachable,
+}
+
+impl From<Lost> for io::Error {
+    fn from(_: Lost) -> Self {
+        io::Error::other("Frontier search lost a state on its path while rebuilding it")
+    }
+}
+
+/// One breadth-first pass out from a state, used both for the main search and for
+/// each of the smaller searches that rebuild the path.
+struct Pass<'a, S> {
+    start: &'a S,
+    /// Cost of reaching `start`, so f-values are compared against the whole path's bound.
+    offset: u32,
+    /// Highest f-value expanded.
+    bound: u32,
+    /// Highest cost from `start` worth going to.
+    cap: u32,
+    /// Children reached across this cost get a relay.
+    middle: u32,
+    /// Whether search limits are checked, which they aren't while rebuilding a solution.
+    limited: bool,
+    /// Whether the best state is kept, which only the main search does.
+    main: bool,
+}
+
+struct Search<'a, P: Puzzle> {
+    puzzle: &'a P,
+    config: &'a SearchConfig,
+    layers: usize,
+    /// Whether paths are rebuilt under the search limits, as a partial solution's is.
+    limited: bool,
+    tracker: ProgressTracker,
+    start_time: Instant,
+    // lowest (h, g) state expanded, reported as the partial solution if a limit is hit
+    best: Option<(u32, u32, Rc<P::State>)>,
+}
+
+/// Finds the cheapest path from `start` to a goal like `search::find_path`, while
+/// keeping only the states waiting to be expanded and the last few layers expanded.
+/// The search runs breadth first by cost, cutting off states whose f-value is over a
+/// bound that's raised pass by pass, as with IDA*. Paths aren't stored: each state
+/// carries just one relay state from the middle of its path, and the path is rebuilt
+/// by searching from the start to the relay and from the relay to the goal, splitting
+/// again in the same way until each part is a single push.
+///
+/// A level is found unsolvable once a pass cuts nothing off. Only states that aren't
+/// known duplicates are cut off, but a pass that let layers go may have forgotten a
+/// state and cut it off again when it came back around a cycle. Such a pass keeps more
+/// layers next time, so that eventually a pass forgets nothing, and from then on every
+/// pass expands states no earlier pass did until there are none left.
+///
+/// If a limit is reached, the path to the best state is rebuilt under the same
+/// limits, so unless it's a single push the partial solution is just the start.
+/// Fails only if a path can't be rebuilt, which would be a bug.
+pub fn find_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+    frontier: &FrontierConfig,
+) -> io::Result<SearchResult<P::Move>> {
+    let mut search = Search {
+        puzzle,
+        config,
+        layers: frontier.layers,
+        limited: false,
+        tracker: ProgressTracker::create(config, Default::default()),
+        start_time: Instant::now(),
+        best: None,
+    };
+
+    let mut bound = puzzle.heuristic(start);
+
+    let outcome = loop {
+        let pass = Pass {
+            start,
+            offset: 0,
+            bound,
+            cap: u32::MAX,
+            middle: std::cmp::max(bound / 2, 1),
+            limited: true,
+            main: true,
+        };
+
+        match search.pass(&pass, |state| puzzle.is_goal_state(state)) {
+            Layered::Found(goal, cost, relay) => {
+                search.tracker.stats.solution_cost = Some(cost);
+                let path = search.split(start, 0, &goal, cost, relay, bound)?;
+                break Outcome::Solved(path);
+            }
+            Layered::Exhausted(Some(next), complete) => {
+                if !complete {
+                    search.layers = std::cmp::max(search.layers * 2, 1);
+                }
+                bound = next;
+            }
+            Layered::Exhausted(None, _) => break Outcome::Unsolvable,
+            Layered::LimitReached(limit) => {
+                let (heuristic, cost, state) = search.best.clone().unwrap();
+                search.limited = true;
+                let partial = match search.rebuild(start, 0, &state, cost, u32::MAX) {
+                    Ok(path) => Partial { path, heuristic },
+                    Err(Lost::Limit) => Partial {
+                        path: Vec::new(),
+                        heuristic: puzzle.heuristic(start),
+                    },
+                    Err(lost) => return Err(lost.into()),
+                };
+                break Outcome::LimitReached(limit, partial);
+            }
+        }
+    };
+
+    Ok(SearchResult {
+        outcome,
+        stats: search.tracker.finish(),
+    })
+}
+
+impl<P: Puzzle> Search<'_, P> {
+    fn pass(
+        &mut self,
+        pass: &Pass<P::State>,
+        is_target: impl Fn(&P::State) -> bool,
+    ) -> Layered<P::State> {
+        let state_bytes = 2 * size_of::<usize>() + self.puzzle.state_size(pass.start);
+
+        let mut open = BTreeMap::new();
+        let mut first = Layer::new();
+        let start = Rc::new(pass.start.clone());
+        first.index.insert(start.clone());
+        first.states.push((start, None));
+        open.insert(0, first);
+
+        let mut recent: VecDeque<HashSet<Rc<P::State>>> = VecDeque::new();
+        let mut complete = true;
+        let mut next_bound = None;
+
+        while let Some((g, layer)) = open.pop_first() {
+            for (state, relay) in layer.states {
+                let h = self.puzzle.heuristic(&state);
+
+                let waiting = open.values().map(|layer| layer.states.len()).sum::<usize>();
+                let memory =
+                    (waiting + recent.iter().map(HashSet::len).sum::<usize>()) * state_bytes;
+                self.tracker.update(pass.offset + g, h, waiting, memory);
+
+                if is_target(&state) {
+                    return Layered::Found(state, g, relay);
+                }
+
+                if pass.main
+                    && self
+                        .best
+                        .as_ref()
+                        .is_none_or(|(best_h, best_g, _)| (h, g) < (*best_h, *best_g))
+                {
+                    self.best = Some((h, g, state.clone()));
+                }
+
+                if pass.limited {
+                    if let Some(limit) =
+                        search::check_limits(self.config, &self.tracker.stats, self.start_time)
+                    {
+                        return Layered::LimitReached(limit);
+                    }
+                }
+
+                let children = self
+                    .puzzle
+                    .create_children_counted(&state, &mut self.tracker.stats.prunes);
+                self.tracker.stats.nodes_generated += children.len() as u64;
+
+                for (child, actions) in children {
+                    let child_g = g + self.puzzle.cost(&actions, self.config.metric);
+                    let f = pass.offset + child_g + self.puzzle.heuristic(&child);
+
+                    if child_g > pass.cap {
+                        continue;
+                    }
+
+                    // duplicates are caught first, so that they don't raise the next bound
+                    let seen = recent.iter().any(|layer| layer.contains(&child))
+                        || layer.index.contains(&child)
+                        || open
+                            .range(..=child_g)
+                            .any(|(_, layer)| layer.index.contains(&child));
+                    if seen {
+                        self.tracker.stats.duplicates += 1;
+                        continue;
+                    }
+
+                    if f > pass.bound {
+                        next_bound = Some(next_bound.map_or(f, |next: u32| next.min(f)));
+                        continue;
+                    }
+
+                    let child = Rc::new(child);
+                    let child_relay = match relay {
+                        Some(_) => relay.clone(),
+                        None if g < pass.middle && child_g >= pass.middle => {
+                            // a relay at the start wouldn't split the path at all
+                            if g == 0 {
+                                Some((child.clone(), child_g))
+                            } else {
+                                Some((state.clone(), g))
+                            }
+                        }
+                        None => None,
+                    };
+
+                    let next = open.entry(child_g).or_insert_with(Layer::new);
+                    next.index.insert(child.clone());
+                    next.states.push((child, child_relay));
+                }
+            }
+
+            recent.push_back(layer.index);
+            if recent.len() > self.layers {
+                recent.pop_front();
+                complete = false;
+            }
+        }
+
+        Layered::Exhausted(next_bound, complete)
+    }
+
+    /// The moves from `from`, reached at cost `offset`, to `to` at `cost` further on,
+    /// by way of `relay` if there is one. `bound` cuts off states that couldn't be on
+    /// a path of that length.
+    fn split(
+        &mut self,
+        from: &P::State,
+        offset: u32,
+        to: &P::State,
+        cost: u32,
+        relay: Option<Relay<P::State>>,
+        bound: u32,
+    ) -> Result<Vec<P::Move>, Lost> {
+        match relay {
+            Some((relay, relay_cost)) => {
+                let mut path = self.rebuild(from, offset, &relay, relay_cost, bound)?;
+                path.extend(self.rebuild(
+                    &relay,
+                    offset + relay_cost,
+                    to,
+                    cost - relay_cost,
+                    bound,
+                )?);
+                Ok(path)
+            }
+            None => self.rebuild(from, offset, to, cost, bound),
+        }
+    }
+
+    /// Searches again from `from` to `to`, a state known to be `cost` away, and
+    /// splits the path there at the relay it finds.
+    fn rebuild(
+        &mut self,
+        from: &P::State,
+        offset: u32,
+        to: &P::State,
+        cost: u32,
+        bound: u32,
+    ) -> Result<Vec<P::Move>, Lost> {
+        if from == to {
+            return Ok(Vec::new());
+        }
+
+        let step = self
+            .puzzle
+            .create_children_counted(from, &mut Default::default())
+            .into_iter()
+            .find(|(child, actions)| {
+                child == to && self.puzzle.cost(actions, self.config.metric) == cost
+            });
+        if let Some((_, actions)) = step {
+            return Ok(actions.iter().rev().copied().collect());
+        }
+
+        let pass = Pass {
+            start: from,
+            offset,
+            bound,
+            cap: cost,
+            middle: std::cmp::max(cost / 2, 1),
+            limited: self.limited,
+            main: false,
+        };
+
+        match self.pass(&pass, |state| state == to) {
+            // found with no relay only if it's closer than thought, which splits next time
+            Layered::Found(_, found, relay) => self.split(from, offset, to, found, relay, bound),
+            Layered::LimitReached(_) => Err(Lost::Limit),
+            Layered::Exhausted(..) => Err(Lost::Unreachable),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::board::{Board, BoardState};
+use crate::lurd;
+use crate::rng::Rng;
+use crate::search::{self, Limits, Metric, SearchConfig};
+
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+/// Fraction of the area inside the outer wall that gets carved out as floor.
+const FLOOR_DENSITY: f64 = 0.55;
+
+/// Chance at each step of the carving walk of opening up a small room.
+const ROOM_CHANCE: f64 = 0.15;
+
+#[derive(Clone, Debug)]
+pub struct GenerateConfig {
+    /// Size of the level including its outer wall.
+    pub width: usize,
+    pub height: usize,
+    pub crates: usize,
+    /// A level is only kept if its optimal push count falls in this range.
+    pub pushes: RangeInclusive<usize>,
+    /// How many pulls to scramble the crates off their goals with.
+    pub scramble: usize,
+    /// Limits for verifying each candidate with the solver.
+    pub limits: Limits,
+}
+
+pub struct Generated {
+    pub level: String,
+    pub solution: String,
+    pub moves: usize,
+    pub pushes: usize,
+}
+
+/// Tries up to `attempts` candidate levels, returning the first one the solver
+/// proves lands in the configured push range.
+pub fn generate(config: &GenerateConfig, rng: &mut Rng, attempts: usize) -> Option<Generated> {
+    (0..attempts).find_map(|_| {
+        let (board, start) = candidate(config, rng)?;
+        verify(config, &board, &start)
+    })
+}
+
+fn verify(config: &GenerateConfig, board: &Board, start: &BoardState) -> Option<Generated> {
+    let search_config = SearchConfig {
+        progress: false,
+        metric: Metric::Pushes,
+        limits: config.limits.clone(),
+        ..SearchConfig::default()
+    };
+
+    let result = search::find_path(board, start, &search_config);
+    let solution = lurd::format(board, start, result.solution()?);
+    let pushes = solution.chars().filter(char::is_ascii_uppercase).count();
+
+    if config.pushes.contains(&pushes) {
+        Some(Generated {
+            level: board.level_string(start),
+            moves: solution.len(),
+            pushes,
+            solution,
+        })
+    } else {
+        None
+    }
+}
+
+fn candidate(config: &GenerateConfig, rng: &mut Rng) -> Option<(Board, BoardState)> {
+    let (width, height) = (config.width, config.height);
+    if width < 3 || height < 3 {
+        return None;
+    }
+
+    let walls = carve(width, height, rng);
+
+    let mut floor: Vec<_> = (0..walls.len()).filter(|i| !walls[*i]).collect();
+    if floor.len() <= config.crates {
+        return None;
+    }
+    rng.shuffle(&mut floor);
+
+    let mut level: Vec<char> = walls.iter().map(|w| if *w { '#' } else { ' ' }).collect();
+    for i in floor.iter().take(config.crates) {
+        level[*i] = '*';
+    }
+    level[floor[config.crates]] = '@';
+
+    let level: String = level
+        .chunks(width)
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect();
+
+    let (board, solved) = Board::parse_level_string(&level).ok()?;
+    let start = scramble(&board, &solved, config.scramble, rng)?;
+
+    Some((board, start))
+}
+
+/// Pulls crates away from the goals at random, avoiding crate layouts it has already been through.
+pub fn scramble(
+    board: &Board,
+    solved: &BoardState,
+    pulls: usize,
+    rng: &mut Rng,
+) -> Option<BoardState> {
+    let mut seen = HashSet::new();
+    seen.insert(board.iter_crates(solved).collect::<Vec<_>>());
+
+    // the player could be anywhere once the level is solved
+    let mut state = rng
+        .choose(&board.create_parents_any_player(solved))?
+        .0
+        .clone();
+
+    for _ in 1..pulls {
+        seen.insert(board.iter_crates(&state).collect::<Vec<_>>());
+
+        let parents: Vec<_> = board
+            .create_parents(&state)
+            .into_iter()
+            .filter(|(parent, _)| !seen.contains(&board.iter_crates(parent).collect::<Vec<_>>()))
+            .collect();
+
+        match rng.choose(&parents) {
+            Some((parent, _)) => state = parent.clone(),
+            None => break,
+        }
+    }
+
+    Some(state)
+}
+
+/// Carves a connected area of floor out of solid wall with a random walk, leaving the outer wall intact.
+fn carve(width: usize, height: usize, rng: &mut Rng) -> Vec<bool> {
+    let mut walls = vec![true; width * height];
+
+    let inner = (width - 2) * (height - 2);
+    let target = ((inner as f64 * FLOOR_DENSITY) as usize).max(1);
+
+    let mut x = 1 + rng.below(width - 2);
+    let mut y = 1 + rng.below(height - 2);
+    let mut carved = 0;
+
+    let mut open = |walls: &mut Vec<bool>, x: usize, y: usize| {
+        if walls[y * width + x] {
+            walls[y * width + x] = false;
+            carved += 1;
+        }
+        carved
+    };
+
+    // bounded in case the walk gets unlucky on a tiny board
+    for _ in 0..inner * 64 {
+        if open(&mut walls, x, y) >= target {
+            break;
+        }
+
+        if rng.unit() < ROOM_CHANCE {
+            for (rx, ry) in [(x + 1, y), (x, y + 1), (x + 1, y + 1)] {
+                if rx < width - 1 && ry < height - 1 {
+                    open(&mut walls, rx, ry);
+                }
+            }
+        }
+
+        match rng.below(4) {
+            0 if x > 1 => x -= 1,
+            1 if x < width - 2 => x += 1,
+            2 if y > 1 => y -= 1,
+            3 if y < height - 2 => y += 1,
+            _ => (),
+        }
+    }
+
+    walls
+}
@@ -0,0 +1,215 @@
+use crate::search::{
+    self, Best, Limit, Node, Outcome, Partial, Path, ProgressTracker, Puzzle, SearchConfig,
+    SearchResult,
+};
+
+use std::collections::{BinaryHeap, HashSet};
+use std::rc::Rc;
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct BeamConfig {
+    /// How many states are kept at each depth. The rest are dropped for good, so a
+    /// narrower beam is faster but more likely to miss every solution. At least 1.
+    pub width: usize,
+}
+
+impl Default for BeamConfig {
+    fn default() -> Self {
+        BeamConfig { width: 1000 }
+    }
+}
+
+/// Greedy best-first search: always expands the state the heuristic puts closest to
+/// a goal, paying no attention to what it cost to reach. On big levels it finds a
+/// solution far sooner than `search::find_path`, though that solution may cost a lot
+/// more than the cheapest. `config.weight` plays no part.
+pub fn find_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+) -> SearchResult<P::Move> {
+    let state_bytes = 2 * size_of::<usize>() + puzzle.state_size(start);
+    let start_time = Instant::now();
+    let mut tracker = ProgressTracker::create(config, Default::default());
+
+    let start = Rc::new(start.clone());
+    let mut seen = HashSet::new();
+    seen.insert(start.clone());
+    let mut heap = BinaryHeap::new();
+    heap.push(node(puzzle, config, start, Rc::new(Path::None), 0, 0));
+    let mut reached = 1;
+
+    // lowest (h, g) node seen so far, reported as the partial solution if a limit is hit
+    let mut best: Option<Best<P::Move>> = None;
+
+    let outcome = loop {
+        let node = match heap.pop() {
+            Some(node) => node,
+            None => break Outcome::Unsolvable,
+        };
+
+        tracker.update(node.g, node.h, heap.len(), seen.len() * state_bytes);
+
+        if puzzle.is_goal_state(&node.state) {
+            tracker.stats.solution_cost = Some(node.g);
+            break Outcome::Solved(search::read_path(&node.path));
+        }
+
+        if best
+            .as_ref()
+            .is_none_or(|(h, g, _)| (node.h, node.g) < (*h, *g))
+        {
+            best = Some((node.h, node.g, node.path.clone()));
+        }
+
+        if let Some(limit) = search::check_limits(config, &tracker.stats, start_time) {
+            break partial(limit, best.unwrap());
+        }
+
+        let children = puzzle.create_children_counted(&node.state, &mut tracker.stats.prunes);
+        tracker.stats.nodes_generated += children.len() as u64;
+
+        for (child, actions) in children {
+            let child = Rc::new(child);
+            if !seen.insert(child.clone()) {
+                tracker.stats.duplicates += 1;
+                continue;
+            }
+
+            let g = node.g + puzzle.cost(&actions, config.metric);
+            let path = Rc::new(Path::Prev(node.path.clone(), actions));
+            heap.push(self::node(puzzle, config, child, path, g, reached));
+            reached += 1;
+        }
+    };
+
+    SearchResult {
+        outcome,
+        stats: tracker.finish(),
+    }
+}
+
+/// Beam search: expands the states one push at a time, keeping only the
+/// `beam.width` states at each depth the heuristic puts closest to a goal. The open
+/// states never outnumber the width, but every state kept is remembered to catch
+/// duplicates, so memory still grows with the width times the depth reached. The
+/// states dropped may have been the only way on, so a level is only reported
+/// unsolvable if none were dropped. Otherwise running out of states stops the search
+/// with `Limit::Width`.
+pub fn beam_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+    beam: &BeamConfig,
+) -> SearchResult<P::Move> {
+    let state_bytes = 2 * size_of::<usize>() + puzzle.state_size(start);
+    let start_time = Instant::now();
+    let mut tracker = ProgressTracker::create(config, Default::default());
+
+    let start = Rc::new(start.clone());
+    let mut seen = HashSet::new();
+    seen.insert(start.clone());
+    let mut layer = vec![node(puzzle, config, start, Rc::new(Path::None), 0, 0)];
+    let mut reached = 1;
+    let mut dropped = false;
+
+    // lowest (h, g) node seen so far, reported as the partial solution if a limit is hit
+    let mut best: Option<Best<P::Move>> = None;
+
+    let outcome = 'search: loop {
+        let mut next = Vec::new();
+        let mut next_seen = HashSet::new();
+
+        let waiting = layer.len();
+        for (i, node) in layer.into_iter().enumerate() {
+            let open = waiting - i + next.len();
+            let memory = (seen.len() + next.len()) * state_bytes;
+            tracker.update(node.g, node.h, open, memory);
+
+            if puzzle.is_goal_state(&node.state) {
+                tracker.stats.solution_cost = Some(node.g);
+                break 'search Outcome::Solved(search::read_path(&node.path));
+            }
+
+            if best
+                .as_ref()
+                .is_none_or(|(h, g, _)| (node.h, node.g) < (*h, *g))
+            {
+                best = Some((node.h, node.g, node.path.clone()));
+            }
+
+            if let Some(limit) = search::check_limits(config, &tracker.stats, start_time) {
+                break 'search partial(limit, best.unwrap());
+            }
+
+            let children = puzzle.create_children_counted(&node.state, &mut tracker.stats.prunes);
+            tracker.stats.nodes_generated += children.len() as u64;
+
+            for (child, actions) in children {
+                let child = Rc::new(child);
+                if seen.contains(&child) || !next_seen.insert(child.clone()) {
+                    tracker.stats.duplicates += 1;
+                    continue;
+                }
+
+                let g = node.g + puzzle.cost(&actions, config.metric);
+                let path = Rc::new(Path::Prev(node.path.clone(), actions));
+                next.push(self::node(puzzle, config, child, path, g, reached));
+                reached += 1;
+            }
+        }
+
+        if next.len() > beam.width {
+            next.sort_unstable_by_key(Node::key);
+            next.truncate(beam.width);
+            dropped = true;
+        }
+
+        if next.is_empty() {
+            if dropped {
+                break partial(Limit::Width, best.unwrap());
+            }
+            break Outcome::Unsolvable;
+        }
+
+        seen.extend(next.iter().map(|node| node.state.clone()));
+        layer = next;
+    };
+
+    SearchResult {
+        outcome,
+        stats: tracker.finish(),
+    }
+}
+
+/// A node ordered by its heuristic alone, then by `config.tie_break`.
+fn node<P: Puzzle>(
+    puzzle: &P,
+    config: &SearchConfig,
+    state: Rc<P::State>,
+    path: Rc<Path<P::Move>>,
+    g: u32,
+    reached: u64,
+) -> Node<P::State, P::Move> {
+    let h = puzzle.heuristic(&state);
+
+    Node {
+        f: h,
+        rank: config.tie_break.rank(puzzle, &state, h),
+        order: config.tie_break.order(reached),
+        state,
+        path,
+        h,
+        g,
+    }
+}
+
+fn partial<M: Copy>(limit: Limit, (heuristic, _, path): Best<M>) -> Outcome<M> {
+    let partial = Partial {
+        path: search::read_path(&path),
+        heuristic,
+    };
+
+    Outcome::LimitReached(limit, partial)
+}
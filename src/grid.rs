@@ -0,0 +1,521 @@
+use crate::board::Prunes;
+use crate::checkpoint::{self, Persist};
+use crate::search::{Children, Puzzle};
+
+use std::collections::VecDeque;
+use std::io;
+use std::marker::PhantomData;
+
+/// The shape of the cells a `GridBoard` is made of, as laid out in the level text.
+///
+/// Only the six-direction grids are built on this. The square `Board` keeps its own
+/// four-way `Action` and tables, which the other variants build on in turn, and the
+/// searches stay agnostic to grid shape through `Puzzle` rather than through this trait.
+pub trait Topology {
+    /// Where a step in `direction` from `(x, y)` in the level text leads, or `None`
+    /// if it runs off the top or left of the text.
+    fn step(position: (usize, usize), direction: Direction) -> Option<(usize, usize)>;
+
+    /// Whether `(x, y)` in the level text holds a cell, given the first character
+    /// in the text is at `first`. Anything else has to be a space.
+    fn is_cell(position: (usize, usize), first: (usize, usize)) -> bool;
+}
+
+/// The six directions a crate can be pushed in, along the rows of the grid and
+/// the two diagonals crossing them.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Direction {
+    West,
+    East,
+    NorthWest,
+    NorthEast,
+    SouthWest,
+    SouthEast,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 6] = [
+        Direction::West,
+        Direction::East,
+        Direction::NorthWest,
+        Direction::NorthEast,
+        Direction::SouthWest,
+        Direction::SouthEast,
+    ];
+
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+            Direction::NorthWest => Direction::SouthEast,
+            Direction::NorthEast => Direction::SouthWest,
+            Direction::SouthWest => Direction::NorthEast,
+            Direction::SouthEast => Direction::NorthWest,
+        }
+    }
+
+    /// The move's letter, taken from the roguelike keys `h l y u b n` and
+    /// uppercased if it pushes a crate.
+    pub fn to_char(self, push: bool) -> char {
+        let c = match self {
+            Direction::West => 'h',
+            Direction::East => 'l',
+            Direction::NorthWest => 'y',
+            Direction::NorthEast => 'u',
+            Direction::SouthWest => 'b',
+            Direction::SouthEast => 'n',
+        };
+
+        if push {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        }
+    }
+}
+
+/// A level on a grid of any `Topology`, with a table of each cell's neighbours
+/// standing in for the index arithmetic a square grid gets away with.
+#[derive(Clone)]
+pub struct GridBoard<T> {
+    /// The neighbouring cell in each direction of `Direction::ALL`, or `None` for a wall.
+    neighbours: Box<[[Option<usize>; 6]]>,
+    goals: Box<[usize]>,
+    goal_cells: Box<[bool]>,
+    dead_cells: Box<[bool]>,
+    /// Number of pushes needed to get a crate from each cell to each goal, if it can be done at all.
+    goal_distances: Box<[Box<[u32]>]>,
+    /// Where each cell sits in the level text.
+    positions: Box<[(usize, usize)]>,
+    walls: Box<[bool]>,
+    width: usize,
+    topology: PhantomData<T>,
+}
+
+/// A position on a `GridBoard`, with cells numbered over the player's area only.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct GridState {
+    player: usize,
+    crates: Box<[bool]>,
+}
+
+impl Persist for Direction {
+    fn write(&self, out: &mut Vec<u8>) {
+        (*self as u8).write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Direction::ALL
+            .get(u8::read(input)? as usize)
+            .copied()
+            .ok_or_else(|| checkpoint::invalid("Checkpoint has an invalid move"))
+    }
+}
+
+impl Persist for GridState {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.player.write(out);
+        checkpoint::write_bits(&self.crates, out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Ok(GridState {
+            player: usize::read(input)?,
+            crates: checkpoint::read_bits(input)?,
+        })
+    }
+}
+
+impl GridState {
+    /// Approximate number of bytes this state occupies, including its crate buffer.
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + std::mem::size_of_val(&*self.crates)
+    }
+}
+
+impl<T: Topology> GridBoard<T> {
+    #[inline]
+    fn neighbour(&self, cell: usize, action: Direction) -> Option<usize> {
+        self.neighbours[cell][action as usize]
+    }
+
+    /// The cell one step from `cell` if it's free for the player or a crate to move into.
+    #[inline]
+    fn empty_neighbour(&self, state: &GridState, cell: usize, action: Direction) -> Option<usize> {
+        self.neighbour(cell, action)
+            .filter(|next| !state.crates[*next])
+    }
+
+    pub fn is_goal_state(&self, state: &GridState) -> bool {
+        self.goals.iter().all(|goal| state.crates[*goal])
+    }
+
+    /// Number of goals with a crate on them.
+    pub fn crates_on_goals(&self, state: &GridState) -> u32 {
+        self.goals
+            .iter()
+            .filter(|goal| state.crates[**goal])
+            .count() as u32
+    }
+
+    pub fn heuristic(&self, state: &GridState) -> u32 {
+        let unsat_goal_dists: Vec<_> = self
+            .goals
+            .iter()
+            .zip(self.goal_distances.iter())
+            .filter(|(goal, _)| !state.crates[**goal])
+            .map(|(_, dists)| dists)
+            .collect();
+
+        // every crate off a goal needs at least as many pushes as it takes to get to the
+        // nearest free goal, a crate that can't get to any of them might still take the
+        // place of a crate that moves off its goal later so it doesn't add anything
+        state
+            .crates
+            .iter()
+            .enumerate()
+            .filter(|(cell, on)| **on && !self.goal_cells[*cell])
+            .map(|(cell, _)| {
+                unsat_goal_dists
+                    .iter()
+                    .map(|dists| dists[cell])
+                    .filter(|dist| *dist != u32::MAX)
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// Generates every state reachable from `state` with a single push. Each child comes
+    /// with the moves that reach it, listed last move first.
+    pub fn create_children(&self, state: &GridState) -> Vec<(GridState, Box<[Direction]>)> {
+        self.create_children_counted(state, &mut Prunes::default())
+    }
+
+    /// `create_children`, also counting the pushes it rules out into `prunes`.
+    pub fn create_children_counted(
+        &self,
+        state: &GridState,
+        prunes: &mut Prunes,
+    ) -> Vec<(GridState, Box<[Direction]>)> {
+        let mut children = Vec::new();
+
+        let mut paths = vec![None; self.neighbours.len()];
+        let mut seen = vec![false; self.neighbours.len()];
+        let mut queue = VecDeque::new();
+
+        let read_path = |paths: &Vec<Option<Direction>>, cell, push| -> Box<[Direction]> {
+            let mut path = vec![push];
+            let mut cell = cell;
+
+            while let Some(action) = paths[cell] {
+                path.push(action);
+                cell = self.neighbour(cell, action.reverse()).unwrap();
+            }
+
+            path.into_boxed_slice()
+        };
+
+        seen[state.player] = true;
+        queue.push_back(state.player);
+
+        while let Some(cell) = queue.pop_front() {
+            for action in Direction::ALL {
+                let next = match self.neighbour(cell, action) {
+                    Some(next) => next,
+                    None => continue,
+                };
+
+                if !state.crates[next] {
+                    if !seen[next] {
+                        seen[next] = true;
+                        paths[next] = Some(action);
+                        queue.push_back(next);
+                    }
+                    continue;
+                }
+
+                let target = match self.empty_neighbour(state, next, action) {
+                    Some(target) => target,
+                    None => continue,
+                };
+
+                if self.dead_cells[target] {
+                    prunes.dead_tile += 1;
+                    continue;
+                }
+
+                let mut child = state.clone();
+                child.crates[next] = false;
+                child.crates[target] = true;
+                child.player = next;
+                children.push((child, read_path(&paths, cell, action)));
+            }
+        }
+
+        children
+    }
+
+    /// Applies a single player move to `state`, pushing a crate if there is one in the way.
+    /// Returns the new state and whether a crate was pushed, or `None` if the move is illegal.
+    pub fn step(&self, state: &GridState, action: Direction) -> Option<(GridState, bool)> {
+        let cell = self.neighbour(state.player, action)?;
+
+        let mut next = state.clone();
+        next.player = cell;
+
+        if !state.crates[cell] {
+            return Some((next, false));
+        }
+
+        let target = self.empty_neighbour(state, cell, action)?;
+        next.crates[cell] = false;
+        next.crates[target] = true;
+        Some((next, true))
+    }
+
+    /// Formats a move list with `Direction::to_char`, replaying it to find the pushes.
+    pub fn format(&self, start: &GridState, path: &[Direction]) -> String {
+        let mut state = start.clone();
+
+        path.iter()
+            .map(|action| {
+                let (next, push) = self.step(&state, *action).expect("illegal move in path");
+                state = next;
+                action.to_char(push)
+            })
+            .collect()
+    }
+
+    /// Draws the board in the level format, with dead cells marked as
+    /// `-` (empty), `%` (player) and `!` (crate).
+    pub fn render(&self, state: &GridState) -> String {
+        let mut tiles: Vec<char> = self
+            .walls
+            .iter()
+            .map(|wall| if *wall { '#' } else { ' ' })
+            .collect();
+
+        for (cell, (x, y)) in self.positions.iter().enumerate() {
+            tiles[y * self.width + x] = match (
+                self.dead_cells[cell],
+                self.goal_cells[cell],
+                state.crates[cell],
+                cell == state.player,
+            ) {
+                (true, _, false, false) => '-',
+                (true, _, false, true) => '%',
+                (true, _, true, _) => '!',
+                (false, true, false, false) => '.',
+                (false, true, false, true) => '+',
+                (false, false, true, _) => '$',
+                (false, true, true, _) => '*',
+                (false, false, false, true) => '@',
+                (false, false, false, false) => ' ',
+            };
+        }
+
+        tiles
+            .chunks(self.width)
+            .map(|row| row.iter().collect::<String>().trim_end().to_string() + "\n")
+            .collect()
+    }
+
+    /// Reads a level in the usual level characters, laid out as `T` places its cells.
+    pub fn parse_level_string(level: &str) -> Result<(Self, GridState), &'static str> {
+        for c in level.chars() {
+            if !"#pPbB@+$*. -_\n".contains(c) {
+                return Err("Level contains invalid character");
+            }
+        }
+
+        let lines: Vec<&str> = level
+            .split('\n')
+            .map(|s| s.trim_end())
+            .skip_while(|s| s.is_empty())
+            .take_while(|s| !s.is_empty())
+            .collect();
+
+        if lines.is_empty() {
+            return Err("Level is empty");
+        }
+
+        let height = lines.len();
+        let width = lines.iter().map(|s| s.len()).max().unwrap();
+
+        let mut tiles = vec![' '; width * height];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                tiles[y * width + x] = c;
+            }
+        }
+
+        let first = tiles.iter().position(|c| *c != ' ').unwrap();
+        let first = (first % width, first / width);
+
+        if (0..tiles.len()).any(|i| tiles[i] != ' ' && !T::is_cell((i % width, i / width), first)) {
+            return Err("Level has a cell off the grid");
+        }
+
+        let players: Vec<_> = (0..tiles.len())
+            .filter(|i| "pP@+".contains(tiles[*i]))
+            .collect();
+
+        if players.is_empty() {
+            return Err("Level has no player");
+        } else if players.len() > 1 {
+            return Err("Level has more than one player");
+        }
+
+        let step = |i: usize, direction: Direction| -> Option<usize> {
+            let (x, y) = T::step((i % width, i / width), direction)?;
+            if x < width && y < height {
+                Some(y * width + x)
+            } else {
+                None
+            }
+        };
+
+        // number the cells the player can reach, which also checks the level is enclosed
+        let mut cells = vec![None; tiles.len()];
+        let mut positions = Vec::new();
+        let mut queue = VecDeque::new();
+
+        queue.push_back(players[0]);
+        cells[players[0]] = Some(0);
+        positions.push(players[0]);
+
+        while let Some(i) = queue.pop_front() {
+            for action in Direction::ALL {
+                let next = step(i, action).ok_or("Player is not enclosed in walls")?;
+
+                if tiles[next] != '#' && cells[next].is_none() {
+                    cells[next] = Some(positions.len());
+                    positions.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let neighbours: Vec<[Option<usize>; 6]> = positions
+            .iter()
+            .map(|i| Direction::ALL.map(|action| step(*i, action).and_then(|next| cells[next])))
+            .collect();
+
+        let mut goals = Vec::new();
+        let mut crates = vec![false; positions.len()];
+        let mut num_crates = 0;
+
+        for (i, c) in tiles.iter().enumerate() {
+            let is_crate = "bB$*".contains(*c);
+            let is_goal = "PB+*.".contains(*c);
+
+            if (is_crate || is_goal) && cells[i].is_none() {
+                return Err("Level has crates or goals the player can't reach");
+            }
+
+            if is_crate {
+                crates[cells[i].unwrap()] = true;
+                num_crates += 1;
+            }
+
+            if is_goal {
+                goals.push(cells[i].unwrap());
+            }
+        }
+
+        if num_crates != goals.len() {
+            return Err("Number of crates and number of goals are not the same");
+        }
+
+        let mut goal_cells = vec![false; positions.len()];
+        for goal in goals.iter() {
+            goal_cells[*goal] = true;
+        }
+
+        let goal_distances: Vec<_> = goals
+            .iter()
+            .map(|goal| push_distances(&neighbours, *goal))
+            .collect();
+
+        // a crate can never be pushed onto a goal from a cell no goal has a distance to
+        let dead_cells: Vec<_> = (0..positions.len())
+            .map(|cell| goal_distances.iter().all(|dists| dists[cell] == u32::MAX))
+            .collect();
+
+        Ok((
+            GridBoard {
+                neighbours: neighbours.into_boxed_slice(),
+                goals: goals.into_boxed_slice(),
+                goal_cells: goal_cells.into_boxed_slice(),
+                dead_cells: dead_cells.into_boxed_slice(),
+                goal_distances: goal_distances.into_boxed_slice(),
+                positions: positions.iter().map(|i| (i % width, i / width)).collect(),
+                walls: tiles.iter().map(|c| *c == '#').collect(),
+                width,
+                topology: PhantomData,
+            },
+            GridState {
+                player: 0,
+                crates: crates.into_boxed_slice(),
+            },
+        ))
+    }
+}
+
+/// The fewest pushes it takes to move a lone crate from each cell onto `goal`, found
+/// by pulling it back out of the goal. Cells it can't be pushed from are `u32::MAX`.
+fn push_distances(neighbours: &[[Option<usize>; 6]], goal: usize) -> Box<[u32]> {
+    let mut dists = vec![u32::MAX; neighbours.len()];
+    let mut queue = VecDeque::new();
+
+    dists[goal] = 0;
+    queue.push_back(goal);
+
+    while let Some(cell) = queue.pop_front() {
+        for push in Direction::ALL {
+            // the crate came from behind the push, with the player behind it again
+            let back = push.reverse() as usize;
+            let from = neighbours[cell][back];
+            let player = from.and_then(|from| neighbours[from][back]);
+
+            if let (Some(from), Some(_)) = (from, player) {
+                if dists[from] == u32::MAX {
+                    dists[from] = dists[cell] + 1;
+                    queue.push_back(from);
+                }
+            }
+        }
+    }
+
+    dists.into_boxed_slice()
+}
+
+impl<T: Topology> Puzzle for GridBoard<T> {
+    type State = GridState;
+    type Move = Direction;
+
+    fn create_children_counted(
+        &self,
+        state: &GridState,
+        prunes: &mut Prunes,
+    ) -> Children<GridState, Direction> {
+        GridBoard::create_children_counted(self, state, prunes)
+    }
+
+    fn heuristic(&self, state: &GridState) -> u32 {
+        GridBoard::heuristic(self, state)
+    }
+
+    fn is_goal_state(&self, state: &GridState) -> bool {
+        GridBoard::is_goal_state(self, state)
+    }
+
+    fn crates_on_goals(&self, state: &GridState) -> u32 {
+        GridBoard::crates_on_goals(self, state)
+    }
+
+    fn state_size(&self, state: &GridState) -> usize {
+        state.size_bytes()
+    }
+}
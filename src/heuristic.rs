@@ -0,0 +1,371 @@
+use crate::board::{Action, Board, BoardState, Prunes};
+use crate::search::{Children, Metric, Puzzle};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// An estimate of how far a state is from being solved, for guiding the search on a
+/// square board. Estimates must never be more than what's left to pay under the
+/// metric, so that A* still finds the cheapest solution.
+pub trait Heuristic {
+    fn estimate(&self, board: &Board, state: &BoardState, metric: Metric) -> u32;
+}
+
+impl<H: Heuristic + ?Sized> Heuristic for Box<H> {
+    fn estimate(&self, board: &Board, state: &BoardState, metric: Metric) -> u32 {
+        (**self).estimate(board, state, metric)
+    }
+}
+
+/// Each crate's distance to the nearest goal without a crate on it, which is what
+/// `Board::heuristic` uses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NearestGoal;
+
+impl Heuristic for NearestGoal {
+    fn estimate(&self, board: &Board, state: &BoardState, _: Metric) -> u32 {
+        board.heuristic(state)
+    }
+}
+
+/// The least total distance over every way of giving each crate a goal of its own,
+/// found with the Hungarian algorithm. Cubic in the number of crates.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Matching;
+
+impl Heuristic for Matching {
+    fn estimate(&self, board: &Board, state: &BoardState, _: Metric) -> u32 {
+        let crates: Vec<usize> = (0..board.tile_count())
+            .filter(|tile| board.is_crate_at(state, *tile))
+            .collect();
+        let costs: Vec<Vec<u32>> = crates
+            .iter()
+            .map(|tile| {
+                board
+                    .goals()
+                    .iter()
+                    .map(|(_, dists)| dists[*tile])
+                    .collect()
+            })
+            .collect();
+
+        min_cost_matching(&costs)
+    }
+}
+
+/// `NearestGoal`, plus 2 for each pair of neighbouring crates off goals that each have
+/// to go through the other's tile to get anywhere near a goal. One of them has to go
+/// around the other, and on a square board any way around is at least 2 pushes longer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LinearConflict;
+
+impl Heuristic for LinearConflict {
+    fn estimate(&self, board: &Board, state: &BoardState, _: Metric) -> u32 {
+        let open_goals: Vec<&[u32]> = board
+            .goals()
+            .iter()
+            .filter(|(goal, _)| !board.is_crate_at(state, *goal))
+            .map(|(_, dists)| &dists[..])
+            .collect();
+
+        // the one push every shortest way to every open goal starts with, if there is one
+        let only_way = |tile: usize| -> Option<Action> {
+            let mut only = None;
+
+            for dists in &open_goals {
+                let closer = dists[tile].checked_sub(1)?;
+                let mut ways = Action::ALL.iter().copied().filter(|action| {
+                    board
+                        .neighbour(tile, *action)
+                        .is_some_and(|next| dists[next] == closer)
+                });
+
+                let way = ways.next()?;
+                if ways.next().is_some() || only.is_some_and(|only| only != way) {
+                    return None;
+                }
+                only = Some(way);
+            }
+
+            only
+        };
+
+        let conflicts = (0..board.tile_count())
+            .filter(|tile| board.is_crate_at(state, *tile) && !board.is_goal_tile(*tile))
+            .filter(|tile| {
+                // each conflict is counted from the crate on its left or top
+                [Action::Right, Action::Down].iter().copied().any(|along| {
+                    board.neighbour(*tile, along).is_some_and(|next| {
+                        board.is_crate_at(state, next)
+                            && !board.is_goal_tile(next)
+                            && only_way(*tile) == Some(along)
+                            && only_way(next) == Some(along.reverse())
+                    })
+                })
+            })
+            .count() as u32;
+
+        board.heuristic(state) + 2 * conflicts
+    }
+}
+
+/// How far the player has to walk to stand next to a crate that's off its goal. Only
+/// moves are counted under `Metric::Moves`, so this is 0 under `Metric::Pushes`, and
+/// it's most useful combined with the others.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerDistance;
+
+impl Heuristic for PlayerDistance {
+    fn estimate(&self, board: &Board, state: &BoardState, metric: Metric) -> u32 {
+        if metric == Metric::Pushes {
+            return 0;
+        }
+
+        let is_target = |tile: usize| {
+            Action::ALL.iter().copied().any(|action| {
+                board
+                    .neighbour(tile, action)
+                    .is_some_and(|next| board.is_crate_at(state, next) && !board.is_goal_tile(next))
+            })
+        };
+
+        let mut seen = vec![false; board.tile_count()];
+        let mut queue = VecDeque::new();
+        queue.push_back((board.player_tile(state), 0));
+
+        while let Some((tile, distance)) = queue.pop_front() {
+            if seen[tile] || board.is_crate_at(state, tile) {
+                continue;
+            }
+            seen[tile] = true;
+
+            if is_target(tile) {
+                return distance;
+            }
+
+            for step in Action::ALL {
+                queue.extend(board.neighbour(tile, step).map(|next| (next, distance + 1)));
+            }
+        }
+
+        0
+    }
+}
+
+/// The exact number of pushes it takes to get two crates onto goals with no other
+/// crates on the board, for every pair of tiles and every region the player could
+/// be in, found by pulling pairs of crates back from every pair of goals. A state's
+/// crates are split into pairs, and their costs added up, since every push moves the
+/// crate of just one pair. A crate left without a pair counts its distance to the
+/// nearest goal.
+///
+/// The table covers every pair of tiles crates can be pulled to, so building it
+/// takes a while on big levels.
+pub struct PatternDatabase {
+    /// Pushes left from the two crate tiles and the player's top-left-most tile.
+    pushes: HashMap<(usize, usize, usize), u32>,
+}
+
+impl PatternDatabase {
+    pub fn new(board: &Board) -> Self {
+        let mut pushes = HashMap::new();
+        let mut queue = VecDeque::new();
+        let goals: Vec<usize> = board.goals().iter().map(|(goal, _)| *goal).collect();
+
+        for (i, first) in goals.iter().enumerate() {
+            for second in &goals[i + 1..] {
+                let state = pair_state(board, *first, *second, 0);
+                let mut regions = HashSet::new();
+
+                for player in
+                    (0..board.tile_count()).filter(|tile| !board.is_crate_at(&state, *tile))
+                {
+                    let mut state = state.clone();
+                    state.set_player(player);
+                    let state = board.normalize_player(&state);
+                    if regions.insert(board.player_tile(&state)) {
+                        queue.push_back((state, 0));
+                    }
+                }
+            }
+        }
+
+        while let Some((state, distance)) = queue.pop_front() {
+            let key = key(board, &state);
+            if pushes.contains_key(&key) {
+                continue;
+            }
+            pushes.insert(key, distance);
+
+            for (parent, _) in board.create_parents(&state) {
+                queue.push_back((board.normalize_player(&parent), distance + 1));
+            }
+        }
+
+        PatternDatabase { pushes }
+    }
+}
+
+impl Heuristic for PatternDatabase {
+    fn estimate(&self, board: &Board, state: &BoardState, _: Metric) -> u32 {
+        let crates: Vec<usize> = (0..board.tile_count())
+            .filter(|tile| board.is_crate_at(state, *tile))
+            .collect();
+        let player = board.player_tile(state);
+
+        let nearest = |tile: usize| {
+            board
+                .goals()
+                .iter()
+                .map(|(_, dists)| dists[tile])
+                .min()
+                .unwrap_or(0)
+        };
+
+        crates
+            .chunks(2)
+            .map(|pair| match *pair {
+                [first, second] => {
+                    let pair = board.normalize_player(&pair_state(board, first, second, player));
+                    // a pair that can't reach goals is deadlocked, which pruning catches
+                    self.pushes
+                        .get(&key(board, &pair))
+                        .copied()
+                        .unwrap_or_else(|| nearest(first) + nearest(second))
+                }
+                [single] => nearest(single),
+                _ => unreachable!(),
+            })
+            .sum()
+    }
+}
+
+/// The highest of several estimates, which is still never too high if none of them are.
+pub struct Max(pub Vec<Box<dyn Heuristic>>);
+
+impl Heuristic for Max {
+    fn estimate(&self, board: &Board, state: &BoardState, metric: Metric) -> u32 {
+        self.0
+            .iter()
+            .map(|heuristic| heuristic.estimate(board, state, metric))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A square board searched with `heuristic` in place of `Board::heuristic`, so that any
+/// search can be run with it.
+pub struct Guided<'a, H> {
+    pub board: &'a Board,
+    pub heuristic: H,
+    /// The metric the search minimises, which some heuristics depend on.
+    pub metric: Metric,
+}
+
+impl<H: Heuristic> Puzzle for Guided<'_, H> {
+    type State = BoardState;
+    type Move = Action;
+
+    fn create_children_counted(
+        &self,
+        state: &BoardState,
+        prunes: &mut Prunes,
+    ) -> Children<BoardState, Action> {
+        self.board.create_children_counted(state, prunes)
+    }
+
+    fn heuristic(&self, state: &BoardState) -> u32 {
+        self.heuristic.estimate(self.board, state, self.metric)
+    }
+
+    fn is_goal_state(&self, state: &BoardState) -> bool {
+        self.board.is_goal_state(state)
+    }
+
+    fn crates_on_goals(&self, state: &BoardState) -> u32 {
+        self.board.crates_on_goals(state)
+    }
+
+    fn state_size(&self, state: &BoardState) -> usize {
+        state.size_bytes()
+    }
+}
+
+fn pair_state(board: &Board, first: usize, second: usize, player: usize) -> BoardState {
+    let mut state = BoardState::new(player, vec![false; board.tile_count()].into());
+    board.set_crate_at(&mut state, first, true);
+    board.set_crate_at(&mut state, second, true);
+    state
+}
+
+fn key(board: &Board, state: &BoardState) -> (usize, usize, usize) {
+    let mut crates = (0..board.tile_count()).filter(|tile| board.is_crate_at(state, *tile));
+    let first = crates.next().expect("pair state has two crates");
+    let second = crates.next().expect("pair state has two crates");
+    (first, second, board.player_tile(state))
+}
+
+/// The least total cost of giving each row its own column, with the Hungarian
+/// algorithm. `costs` must have no more rows than columns.
+fn min_cost_matching(costs: &[Vec<u32>]) -> u32 {
+    let rows = costs.len();
+    let columns = costs.first().map_or(0, Vec::len);
+
+    // potentials and matches are numbered from 1, with 0 standing for none
+    let mut row_potential = vec![0i64; rows + 1];
+    let mut column_potential = vec![0i64; columns + 1];
+    let mut matched = vec![0usize; columns + 1];
+    let mut way = vec![0usize; columns + 1];
+
+    for row in 1..=rows {
+        matched[0] = row;
+        let mut column = 0;
+        let mut least = vec![i64::MAX; columns + 1];
+        let mut used = vec![false; columns + 1];
+
+        while matched[column] != 0 {
+            used[column] = true;
+            let current = matched[column];
+            let mut delta = i64::MAX;
+            let mut next = 0;
+
+            for j in 1..=columns {
+                if used[j] {
+                    continue;
+                }
+
+                let reduced = i64::from(costs[current - 1][j - 1])
+                    - row_potential[current]
+                    - column_potential[j];
+                if reduced < least[j] {
+                    least[j] = reduced;
+                    way[j] = column;
+                }
+                if least[j] < delta {
+                    delta = least[j];
+                    next = j;
+                }
+            }
+
+            for j in 0..=columns {
+                if used[j] {
+                    row_potential[matched[j]] += delta;
+                    column_potential[j] -= delta;
+                } else {
+                    least[j] -= delta;
+                }
+            }
+            column = next;
+        }
+
+        while column != 0 {
+            let previous = way[column];
+            matched[column] = matched[previous];
+            column = previous;
+        }
+    }
+
+    (1..=columns)
+        .filter(|j| matched[*j] != 0)
+        .map(|j| costs[matched[j] - 1][j - 1])
+        .sum()
+}
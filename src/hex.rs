@@ -0,0 +1,34 @@
+use crate::grid::{Direction, GridBoard, Topology};
+
+/// Hexagonal cells, written with the cells in a row separated by spaces and every
+/// other row shifted along by one character:
+///
+/// ```text
+///  # # # #
+/// # @ $ . #
+///  # # # #
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Hex;
+
+pub type HexBoard = GridBoard<Hex>;
+
+impl Topology for Hex {
+    fn step((x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        // a row's cells sit two columns apart, and each row is shifted half a cell from its neighbours
+        let (dx, dy) = match direction {
+            Direction::West => (-2, 0),
+            Direction::East => (2, 0),
+            Direction::NorthWest => (-1, -1),
+            Direction::NorthEast => (1, -1),
+            Direction::SouthWest => (-1, 1),
+            Direction::SouthEast => (1, 1),
+        };
+
+        Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?))
+    }
+
+    fn is_cell((x, y): (usize, usize), (first_x, first_y): (usize, usize)) -> bool {
+        (x + y) % 2 == (first_x + first_y) % 2
+    }
+}
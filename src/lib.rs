@@ -0,0 +1,24 @@
+pub mod anytime;
+pub mod bench;
+pub mod board;
+pub mod certificate;
+pub mod checkpoint;
+pub mod external;
+pub mod frontier;
+pub mod generate;
+pub mod greedy;
+pub mod grid;
+pub mod heuristic;
+pub mod hex;
+pub mod json;
+pub mod lurd;
+pub mod mcts;
+pub mod multi;
+pub mod optimize;
+pub mod pull;
+pub mod rate;
+pub mod rng;
+pub mod search;
+pub mod tiles;
+pub mod tri;
+pub mod typed;
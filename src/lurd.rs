@@ -0,0 +1,47 @@
+use crate::board::{Action, Board, BoardState};
+
+/// The LURD character for a move, uppercase when the move pushes a crate.
+pub fn action_char(action: Action, push: bool) -> char {
+    let c = match action {
+        Action::Up => 'u',
+        Action::Down => 'd',
+        Action::Left => 'l',
+        Action::Right => 'r',
+    };
+
+    if push {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    }
+}
+
+/// Replays `path` from `start` and writes it out in LURD notation.
+/// Panics if the path contains an illegal move.
+pub fn format(board: &Board, start: &BoardState, path: &[Action]) -> String {
+    let mut state = start.clone();
+
+    path.iter()
+        .map(|action| {
+            let (next, push) = board.step(&state, *action).expect("illegal move in path");
+            state = next;
+            action_char(*action, push)
+        })
+        .collect()
+}
+
+/// Reads a solution in LURD notation. Case is ignored, since whether a move
+/// pushes is determined by the level, and whitespace is skipped.
+pub fn parse(solution: &str) -> Result<Vec<Action>, &'static str> {
+    solution
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c.to_ascii_lowercase() {
+            'u' => Ok(Action::Up),
+            'd' => Ok(Action::Down),
+            'l' => Ok(Action::Left),
+            'r' => Ok(Action::Right),
+            _ => Err("Solution contains an invalid character"),
+        })
+        .collect()
+}
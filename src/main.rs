@@ -1,43 +1,1006 @@
-mod search;
-mod board;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-use std::time::Instant;
+use pushcrate::anytime;
+use pushcrate::bench;
+use pushcrate::board::Board;
+use pushcrate::certificate::{self, Certificate, Proof};
+use pushcrate::external::{self, ExternalConfig};
+use pushcrate::frontier::{self, FrontierConfig};
+use pushcrate::generate::{self, GenerateConfig};
+use pushcrate::greedy::{self, BeamConfig};
+use pushcrate::heuristic::{
+    Guided, Heuristic, LinearConflict, Matching, Max, NearestGoal, PatternDatabase, PlayerDistance,
+};
+use pushcrate::hex::HexBoard;
+use pushcrate::json::Json;
+use pushcrate::lurd;
+use pushcrate::mcts::{self, MctsConfig, Rollout};
+use pushcrate::multi::MultiBoard;
+use pushcrate::optimize::{self, OptimizeConfig};
+use pushcrate::pull::{PullBoard, Rules};
+use pushcrate::rate::{self, Rating};
+use pushcrate::rng::Rng;
+use pushcrate::search::{
+    self, CheckpointConfig, Limits, Outcome, Puzzle, SearchConfig, SearchResult, TieBreak,
+};
+use pushcrate::tiles::TileBoard;
+use pushcrate::tri::TriBoard;
+use pushcrate::typed::TypedBoard;
 
-use board::{Action, Board};
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Format {
+    Text,
+    Json,
+}
 
-fn path_to_string(path: &Vec<Action>) -> String {
-    path.into_iter()
-        .map(|a| match a {
-            Action::Up => 'u',
-            Action::Down => 'd',
-            Action::Left => 'l',
-            Action::Right => 'r',
-        })
-        .collect()
+fn usage(program: &str) -> ! {
+    println!(
+        "Usage: {0} [--format text|json] [--variant square|hex|tri|multi|typed|pull|tiles] [--pull-cost <n>] [limits] \
+         [--weight <w>] [--tie-break fifo|lifo|h|goals] [--anytime] [--greedy] [--beam <width>] [--compare] \
+         [--checkpoint <file> [--checkpoint-interval <seconds>] [--resume]] \
+         [--external <dir> [--external-buffer <MiB>]] [--frontier [--frontier-layers <n>]] \
+         [--mcts [--seed <n>] [--rollout random|guided] [--rollout-depth <pushes>]] \
+         [--heuristic nearest|matching|conflict|player|pdb[,...]] [--progress-interval <states>] \
+         <sokoban level file>\n\
+         \x20      {0} bench [--jobs <n>] [--output <file.csv|file.json>] \
+         [--baseline <file.csv>] [limits] <level directory or collection>\n\
+         \x20      {0} generate --width <n> --height <n> --crates <n> [--count <n>] [--seed <n>] \
+         [--min-pushes <n>] [--max-pushes <n>] [--scramble <pulls>] [--attempts <n>] [limits]\n\
+         \x20      {0} rate [--format text|csv|json] [--output <file>] [limits] <levels>...\n\
+         \x20      {0} optimize [--window <pushes>] [limits] <sokoban level file> \
+         <solution file or LURD>\n\
+         \x20      {0} prove [limits] <sokoban level file> <certificate file>\n\
+         \x20      {0} check-certificate <sokoban level file> <certificate file>\n\
+         Limits: [--time-limit <seconds>] [--node-limit <nodes>] [--memory-limit <MiB>]",
+        program
+    );
+    std::process::exit(1);
+}
+
+fn parse_value<T: FromStr>(program: &str, value: Option<&String>) -> T {
+    value
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| usage(program))
+}
+
+/// Reads a number of seconds, which has to be finite and not negative.
+fn parse_seconds(program: &str, value: Option<&String>) -> Duration {
+    Duration::try_from_secs_f64(parse_value(program, value)).unwrap_or_else(|_| usage(program))
+}
+
+/// Reads a heuristic weight, which has to be finite and at least 1.
+fn parse_weight(program: &str, value: Option<&String>) -> f64 {
+    let weight: f64 = parse_value(program, value);
+    if !weight.is_finite() || weight < 1.0 {
+        usage(program);
+    }
+    weight
+}
+
+/// Reads a size in MiB as a number of bytes.
+fn parse_mebibytes(program: &str, value: Option<&String>) -> usize {
+    parse_value::<usize>(program, value)
+        .checked_mul(1 << 20)
+        .unwrap_or_else(|| usage(program))
+}
+
+/// Handles the search limit flags shared by every subcommand, returning false if `arg` isn't one.
+fn parse_limit<'a>(
+    program: &str,
+    arg: &str,
+    iter: &mut impl Iterator<Item = &'a String>,
+    limits: &mut Limits,
+) -> bool {
+    match arg {
+        "--time-limit" => limits.time = Some(parse_seconds(program, iter.next())),
+        "--node-limit" => limits.nodes = Some(parse_value(program, iter.next())),
+        "--memory-limit" => limits.memory = Some(parse_mebibytes(program, iter.next())),
+        _ => return false,
+    }
+
+    true
 }
 
 fn main() -> std::io::Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
-    if args.len() != 2 {
-        println!("Usage: {} <sokoban level file>", args[0]);
-        std::process::exit(1);
+    match args.get(1).map(String::as_str) {
+        Some("bench") => bench(&args[0], &args[2..]),
+        Some("generate") => generate(&args[0], &args[2..]),
+        Some("rate") => rate(&args[0], &args[2..]),
+        Some("optimize") => optimize(&args[0], &args[2..]),
+        Some("prove") => prove(&args[0], &args[2..]),
+        Some("check-certificate") => check_certificate(&args[0], &args[2..]),
+        _ => solve(&args[0], &args[1..]),
     }
+}
 
-    let level_string = std::fs::read_to_string(&args[1])?;
-    let (board, start) = Board::parse_level_string(&level_string).unwrap();
+fn bench(program: &str, args: &[String]) -> std::io::Result<()> {
+    let mut limits = Limits {
+        time: Some(Duration::from_secs(10)),
+        ..Limits::default()
+    };
+    let mut jobs = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut output = None;
+    let mut baseline = None;
+    let mut path = None;
 
-    let start_time = Instant::now();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            _ if parse_limit(program, arg, &mut iter, &mut limits) => (),
+            "--jobs" => jobs = parse_value(program, iter.next()),
+            "--output" => output = Some(iter.next().unwrap_or_else(|| usage(program))),
+            "--baseline" => baseline = Some(iter.next().unwrap_or_else(|| usage(program))),
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => usage(program),
+        }
+    }
+
+    let path = path.unwrap_or_else(|| usage(program));
+    let levels = bench::load_levels(Path::new(path))?;
+
+    let results = bench::run(&levels, &limits, jobs);
+
+    for result in results.iter() {
+        println!(
+            "{:<16} {:<14} {:>6} moves {:>5} pushes {:>10} nodes {:>9.3}s",
+            result.name,
+            result.status,
+            result.moves.map_or("-".to_string(), |n| n.to_string()),
+            result.pushes.map_or("-".to_string(), |n| n.to_string()),
+            result.nodes,
+            result.time
+        );
+    }
+
+    let solved = results.iter().filter(|r| r.is_solved()).count();
+    println!("Solved {} of {} levels.", solved, results.len());
+
+    if let Some(output) = output {
+        let table = if output.ends_with(".json") {
+            bench::to_json(&results).to_string()
+        } else {
+            bench::to_csv(&results)
+        };
+        std::fs::write(output, table)?;
+    }
+
+    if let Some(baseline) = baseline {
+        let baseline = bench::parse_csv(&std::fs::read_to_string(baseline)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        let regressions = bench::compare(&baseline, &results);
+        for regression in regressions.iter() {
+            println!("Regression: {}", regression);
+        }
+
+        if !regressions.is_empty() {
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
+
+fn generate(program: &str, args: &[String]) -> std::io::Result<()> {
+    let mut width = None;
+    let mut height = None;
+    let mut crates = None;
+    let mut count = 1;
+    let mut seed = None;
+    let mut min_pushes = 1;
+    let mut max_pushes = usize::MAX;
+    let mut scramble = None;
+    let mut attempts = 1000;
+    let mut limits = Limits {
+        time: Some(Duration::from_secs(5)),
+        ..Limits::default()
+    };
 
-    match search::find_path(&board, &start) {
-        Some(path) => println!("Found solution: {}", path_to_string(&path)),
-        None => println!("Exhausted search, level is not solvable."),
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            _ if parse_limit(program, arg, &mut iter, &mut limits) => (),
+            "--width" => width = Some(parse_value(program, iter.next())),
+            "--height" => height = Some(parse_value(program, iter.next())),
+            "--crates" => crates = Some(parse_value(program, iter.next())),
+            "--count" => count = parse_value(program, iter.next()),
+            "--seed" => seed = Some(parse_value(program, iter.next())),
+            "--min-pushes" => min_pushes = parse_value(program, iter.next()),
+            "--max-pushes" => max_pushes = parse_value(program, iter.next()),
+            "--scramble" => scramble = Some(parse_value(program, iter.next())),
+            "--attempts" => attempts = parse_value(program, iter.next()),
+            _ => usage(program),
+        }
     }
 
+    let crates = crates.unwrap_or_else(|| usage(program));
+    let config = GenerateConfig {
+        width: width.unwrap_or_else(|| usage(program)),
+        height: height.unwrap_or_else(|| usage(program)),
+        crates,
+        pushes: min_pushes..=max_pushes,
+        scramble: scramble.unwrap_or(crates * 20),
+        limits,
+    };
+
+    let seed = seed.unwrap_or_else(|| Rng::from_time().next_u64());
+    let mut rng = Rng::new(seed);
+    println!("; seed {}", seed);
+
+    for i in 1..=count {
+        match generate::generate(&config, &mut rng, attempts) {
+            Some(level) => {
+                println!();
+                println!(
+                    "; Level {}: {} pushes, {} moves",
+                    i, level.pushes, level.moves
+                );
+                print!("{}", level.level);
+            }
+            None => {
+                println!("; Gave up on level {} after {} attempts", i, attempts);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn rate(program: &str, args: &[String]) -> std::io::Result<()> {
+    let mut format = "text";
+    let mut output = None;
+    let mut limits = Limits {
+        time: Some(Duration::from_secs(10)),
+        ..Limits::default()
+    };
+    let mut levels = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            _ if parse_limit(program, arg, &mut iter, &mut limits) => (),
+            "--format" => match iter.next().map(String::as_str) {
+                Some(f @ ("text" | "csv" | "json")) => format = f,
+                _ => usage(program),
+            },
+            "--output" => output = Some(iter.next().unwrap_or_else(|| usage(program))),
+            _ if !arg.starts_with("--") => {
+                let mut loaded = bench::load_levels(Path::new(arg))?;
+                // a file holding one unnamed level is named after the file instead
+                if let [level] = loaded.as_mut_slice() {
+                    if Path::new(arg).is_file() && level.name == "1" {
+                        level.name = arg.clone();
+                    }
+                }
+                levels.extend(loaded);
+            }
+            _ => usage(program),
+        }
+    }
+
+    if levels.is_empty() {
+        usage(program);
+    }
+
+    let config = SearchConfig {
+        progress: false,
+        limits,
+        ..SearchConfig::default()
+    };
+
+    let mut ratings: Vec<_> = levels
+        .iter()
+        .map(|level| {
+            let rating = Board::parse_level_string(&level.text)
+                .ok()
+                .and_then(|(board, start)| rate::rate(&board, &start, &config));
+            (level.name.as_str(), rating)
+        })
+        .collect();
+
+    // easiest first, with anything that couldn't be rated at the end
+    ratings.sort_by(|(_, a), (_, b)| {
+        let key = |r: &Option<Rating>| r.as_ref().map_or(f64::INFINITY, |r| r.score);
+        key(a).total_cmp(&key(b))
+    });
+
+    let table = match format {
+        "json" => {
+            let rows = ratings.iter().map(|(name, rating)| {
+                let mut row = vec![("level", (*name).into())];
+                match rating.as_ref().map(Rating::to_json) {
+                    Some(Json::Object(fields)) => row.extend(fields),
+                    _ => row.push(("score", Json::Null)),
+                }
+                Json::Object(row)
+            });
+            Json::Array(rows.collect()).to_string() + "\n"
+        }
+        "csv" => {
+            let mut csv = format!("level,{}\n", rate::CSV_HEADER);
+            for (name, rating) in ratings.iter() {
+                let row = rating.as_ref().map_or(",,,,,,".to_string(), Rating::to_csv);
+                csv.push_str(&format!("{},{}\n", name, row));
+            }
+            csv
+        }
+        _ => ratings
+            .iter()
+            .map(|(name, rating)| match rating {
+                Some(r) => format!(
+                    "{:<16} score {:>7.2}  {:>10} nodes  branching {:>5.2}  {:>5} pushes  \
+                     {:>8} prunes  {:>4.0}% against greedy\n",
+                    name,
+                    r.score,
+                    r.nodes_expanded,
+                    r.branching_factor,
+                    r.pushes,
+                    r.deadlock_prunes,
+                    r.greedy_deviation * 100.0
+                ),
+                None => format!("{:<16} not solved\n", name),
+            })
+            .collect(),
+    };
+
+    match output {
+        Some(output) => std::fs::write(output, table)?,
+        None => print!("{}", table),
+    }
+
+    Ok(())
+}
+
+fn optimize(program: &str, args: &[String]) -> std::io::Result<()> {
+    let mut config = OptimizeConfig::default();
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            _ if parse_limit(program, arg, &mut iter, &mut config.limits) => (),
+            "--window" => config.window = parse_value(program, iter.next()),
+            _ if !arg.starts_with("--") => positional.push(arg),
+            _ => usage(program),
+        }
+    }
+
+    let (level_file, solution) = match positional[..] {
+        [level_file, solution] => (level_file, solution),
+        _ => usage(program),
+    };
+
+    let level_string = std::fs::read_to_string(level_file)?;
+    let solution = if Path::new(solution).is_file() {
+        std::fs::read_to_string(solution)?
+    } else {
+        solution.clone()
+    };
+
+    let parsed = Board::parse_level_string(&level_string)
+        .and_then(|(board, start)| Ok((board, start, lurd::parse(&solution)?)));
+    let (board, start, path) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let optimized = match optimize::optimize(&board, &start, &path, &config) {
+        Ok(optimized) => optimized,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let original = lurd::format(&board, &start, &path);
+    let improved = lurd::format(&board, &start, &optimized.path);
+
+    let pushes = |lurd: &str| lurd.chars().filter(char::is_ascii_uppercase).count();
+
+    println!(
+        "Original:  {} moves, {} pushes",
+        original.len(),
+        pushes(&original)
+    );
     println!(
-        "Search finished after {} seconds.",
-        start_time.elapsed().as_secs_f64()
+        "Optimized: {} moves, {} pushes ({} pushes cut from cycles, {} windows improved)",
+        improved.len(),
+        pushes(&improved),
+        optimized.cycle_pushes,
+        optimized.improved_windows
     );
+    println!("{}", improved);
+
+    Ok(())
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Variant {
+    Square,
+    Hex,
+    Tri,
+    Multi,
+    Typed,
+    Pull,
+    Tiles,
+}
+
+fn prove(program: &str, args: &[String]) -> std::io::Result<()> {
+    let mut config = SearchConfig::default();
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            _ if parse_limit(program, arg, &mut iter, &mut config.limits) => (),
+            _ if !arg.starts_with("--") => positional.push(arg),
+            _ => usage(program),
+        }
+    }
+
+    let (level_file, certificate_file) = match positional[..] {
+        [level_file, certificate_file] => (level_file, certificate_file),
+        _ => usage(program),
+    };
+
+    let level_string = std::fs::read_to_string(level_file)?;
+    let (board, start) = match Board::parse_level_string(&level_string) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match certificate::prove_unsolvable(&board, &start, &config) {
+        Proof::Unsolvable(certificate) => {
+            std::fs::write(certificate_file, certificate.to_text(&board))?;
+            println!(
+                "Level is not solvable, certificate of {} states written to {}.",
+                certificate.states.len(),
+                certificate_file
+            );
+        }
+        Proof::Solvable => {
+            println!("Level is solvable, there's nothing to prove.");
+            std::process::exit(1);
+        }
+        Proof::LimitReached(limit) => {
+            println!("Proof stopped, {} limit reached.", limit.name());
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+fn check_certificate(program: &str, args: &[String]) -> std::io::Result<()> {
+    let (level_file, certificate_file) = match args {
+        [level_file, certificate_file] => (level_file, certificate_file),
+        _ => usage(program),
+    };
+
+    let level_string = std::fs::read_to_string(level_file)?;
+    let (board, start) = match Board::parse_level_string(&level_string) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let certificate_string = std::fs::read_to_string(certificate_file)?;
+
+    let checked = Certificate::parse(&board, &certificate_string)
+        .and_then(|certificate| certificate::check(&board, &start, &certificate));
+
+    match checked {
+        Ok(()) => println!("Certificate is valid, level is not solvable."),
+        Err(e) => {
+            println!("Certificate is invalid: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// How `solve` searches: from scratch, carrying on from a checkpoint, on disk,
+/// keeping only the frontier, improving on solutions as it finds them, settling for
+/// any solution at all, or growing a Monte Carlo tree.
+enum Mode {
+    Search,
+    Resume,
+    External(ExternalConfig),
+    Frontier(FrontierConfig),
+    Anytime,
+    Greedy,
+    Beam(BeamConfig),
+    Mcts(MctsConfig),
+}
+
+const HEURISTICS: [&str; 5] = ["nearest", "matching", "conflict", "player", "pdb"];
+
+/// Reads a comma-separated list of heuristics to combine.
+fn parse_heuristics<'a>(program: &str, names: &'a str) -> Vec<&'a str> {
+    names
+        .split(',')
+        .inspect(|name| {
+            if !HEURISTICS.contains(name) {
+                usage(program);
+            }
+        })
+        .collect()
+}
+
+/// The heuristics named, taking the highest of their estimates if there's more than one.
+fn build_heuristic(board: &Board, names: &[&str]) -> Box<dyn Heuristic> {
+    let mut heuristics: Vec<Box<dyn Heuristic>> = names
+        .iter()
+        .map(|name| -> Box<dyn Heuristic> {
+            match *name {
+                "nearest" => Box::new(NearestGoal),
+                "matching" => Box::new(Matching),
+                "conflict" => Box::new(LinearConflict),
+                "player" => Box::new(PlayerDistance),
+                _ => Box::new(PatternDatabase::new(board)),
+            }
+        })
+        .collect();
+
+    match heuristics.len() {
+        1 => heuristics.pop().unwrap(),
+        _ => Box::new(Max(heuristics)),
+    }
+}
+
+/// How `solve` searches, and what it reports.
+struct SolveOptions {
+    format: Format,
+    mode: Mode,
+    /// Compare a solution with the cheapest one.
+    compare: bool,
+}
+
+/// Switches `solve` to `mode`, as long as it hasn't been switched to another already.
+fn switch_mode(program: &str, current: Mode, mode: Mode) -> Mode {
+    match current {
+        Mode::Search => mode,
+        _ => usage(program),
+    }
+}
+
+fn solve(program: &str, args: &[String]) -> std::io::Result<()> {
+    let mut format = Format::Text;
+    let mut variant = Variant::Square;
+    let mut rules = Rules::default();
+    let mut limits = Limits::default();
+    let mut tie_break = None;
+    let mut checkpoint = None;
+    let mut interval = Duration::from_secs(60);
+    let mut weight = None;
+    let mut mode = Mode::Search;
+    let mut external_buffer = None;
+    let mut frontier_layers = None;
+    let mut compare = false;
+    let mut seed = None;
+    let mut rollout = None;
+    let mut rollout_depth = None;
+    let mut heuristics = None;
+    let mut progress_interval = SearchConfig::default().progress_interval;
+    let mut level_file = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            _ if parse_limit(program, arg, &mut iter, &mut limits) => (),
+            "--format" => match iter.next().map(String::as_str) {
+                Some("text") => format = Format::Text,
+                Some("json") => format = Format::Json,
+                _ => usage(program),
+            },
+            "--variant" => match iter.next().map(String::as_str) {
+                Some("square") => variant = Variant::Square,
+                Some("hex") => variant = Variant::Hex,
+                Some("tri") => variant = Variant::Tri,
+                Some("multi") => variant = Variant::Multi,
+                Some("typed") => variant = Variant::Typed,
+                Some("pull") => variant = Variant::Pull,
+                Some("tiles") => variant = Variant::Tiles,
+                _ => usage(program),
+            },
+            "--pull-cost" => rules.pull_cost = parse_value(program, iter.next()),
+            "--tie-break" => match iter.next().map(String::as_str) {
+                Some("fifo") => tie_break = Some(TieBreak::Fifo),
+                Some("lifo") => tie_break = Some(TieBreak::Lifo),
+                Some("h") => tie_break = Some(TieBreak::LowerH),
+                Some("goals") => tie_break = Some(TieBreak::MoreOnGoals),
+                _ => usage(program),
+            },
+            "--checkpoint" => checkpoint = Some(iter.next().unwrap_or_else(|| usage(program))),
+            "--checkpoint-interval" => interval = parse_seconds(program, iter.next()),
+            "--weight" => weight = Some(parse_weight(program, iter.next())),
+            "--progress-interval" => progress_interval = parse_value(program, iter.next()),
+            "--resume" => mode = switch_mode(program, mode, Mode::Resume),
+            "--external" => {
+                let dir = iter.next().unwrap_or_else(|| usage(program));
+                let external = ExternalConfig {
+                    dir: dir.into(),
+                    ..ExternalConfig::default()
+                };
+                mode = switch_mode(program, mode, Mode::External(external));
+            }
+            "--external-buffer" => external_buffer = Some(parse_mebibytes(program, iter.next())),
+            "--frontier" => {
+                let frontier = FrontierConfig::default();
+                mode = switch_mode(program, mode, Mode::Frontier(frontier));
+            }
+            "--frontier-layers" => frontier_layers = Some(parse_value(program, iter.next())),
+            "--anytime" => mode = switch_mode(program, mode, Mode::Anytime),
+            "--greedy" => mode = switch_mode(program, mode, Mode::Greedy),
+            "--beam" => {
+                let beam = BeamConfig {
+                    width: parse_value(program, iter.next()),
+                };
+                // with nothing kept, every search would stop at once for want of width
+                if beam.width == 0 {
+                    usage(program);
+                }
+                mode = switch_mode(program, mode, Mode::Beam(beam));
+            }
+            "--compare" => compare = true,
+            "--mcts" => mode = switch_mode(program, mode, Mode::Mcts(MctsConfig::default())),
+            "--seed" => seed = Some(parse_value(program, iter.next())),
+            "--rollout" => match iter.next().map(String::as_str) {
+                Some("random") => rollout = Some(Rollout::Random),
+                Some("guided") => rollout = Some(Rollout::Guided),
+                _ => usage(program),
+            },
+            "--rollout-depth" => rollout_depth = Some(parse_value(program, iter.next())),
+            "--heuristic" => {
+                let names = iter.next().unwrap_or_else(|| usage(program));
+                heuristics = Some(parse_heuristics(program, names));
+            }
+            _ if level_file.is_none() && !arg.starts_with("--") => level_file = Some(arg),
+            _ => usage(program),
+        }
+    }
+
+    let level_file = level_file.unwrap_or_else(|| usage(program));
+    // only plain A* knows how to checkpoint, only it and anytime A* weigh the heuristic,
+    // and external, frontier and MCTS search don't break ties
+    let weighted = matches!(mode, Mode::Search | Mode::Resume | Mode::Anytime);
+    let untied = matches!(mode, Mode::External(_) | Mode::Frontier(_) | Mode::Mcts(_));
+    if checkpoint.is_some() && !matches!(mode, Mode::Search | Mode::Resume) {
+        usage(program);
+    }
+    if (weight.is_some() && !weighted) || (tie_break.is_some() && untied) {
+        usage(program);
+    }
+
+    match &mut mode {
+        Mode::Resume if checkpoint.is_none() => usage(program),
+        Mode::External(external) => external.buffer = external_buffer.unwrap_or(external.buffer),
+        Mode::Frontier(frontier) => frontier.layers = frontier_layers.unwrap_or(frontier.layers),
+        Mode::Mcts(mcts) => {
+            mcts.seed = seed.unwrap_or(mcts.seed);
+            mcts.rollout = rollout.unwrap_or(mcts.rollout);
+            mcts.depth = rollout_depth.unwrap_or(mcts.depth);
+        }
+        _ if external_buffer.is_some() || frontier_layers.is_some() => usage(program),
+        _ if seed.is_some() || rollout.is_some() || rollout_depth.is_some() => usage(program),
+        _ => (),
+    }
+
+    if heuristics.is_some() && variant != Variant::Square {
+        usage(program);
+    }
+
+    let level_string = std::fs::read_to_string(level_file)?;
+
+    let config = SearchConfig {
+        progress: format == Format::Text,
+        progress_interval,
+        // anytime search needs a weight to find its first solution quickly
+        weight: weight.unwrap_or(if let Mode::Anytime = mode { 2.0 } else { 1.0 }),
+        tie_break: tie_break.unwrap_or(SearchConfig::default().tie_break),
+        limits,
+        checkpoint: checkpoint.map(|path| CheckpointConfig {
+            path: path.into(),
+            interval,
+        }),
+        ..SearchConfig::default()
+    };
+
+    let options = SolveOptions {
+        format,
+        mode,
+        compare,
+    };
+
+    match variant {
+        Variant::Square => {
+            let (board, start) = level_or_exit(format, Board::parse_level_string(&level_string));
+            match &heuristics {
+                Some(names) => {
+                    let guided = Guided {
+                        board: &board,
+                        heuristic: build_heuristic(&board, names),
+                        metric: config.metric,
+                    };
+                    report(
+                        &guided,
+                        &start,
+                        &config,
+                        &options,
+                        board.render(&start),
+                        |path| lurd::format(&board, &start, path),
+                    )?;
+                }
+                None => report(
+                    &board,
+                    &start,
+                    &config,
+                    &options,
+                    board.render(&start),
+                    |path| lurd::format(&board, &start, path),
+                )?,
+            }
+        }
+        Variant::Hex => {
+            let (board, start) = level_or_exit(format, HexBoard::parse_level_string(&level_string));
+            report(
+                &board,
+                &start,
+                &config,
+                &options,
+                board.render(&start),
+                |path| board.format(&start, path),
+            )?;
+        }
+        Variant::Tri => {
+            let (board, start) = level_or_exit(format, TriBoard::parse_level_string(&level_string));
+            report(
+                &board,
+                &start,
+                &config,
+                &options,
+                board.render(&start),
+                |path| board.format(&start, path),
+            )?;
+        }
+        Variant::Multi => {
+            let (board, start) =
+                level_or_exit(format, MultiBoard::parse_level_string(&level_string));
+            report(
+                &board,
+                &start,
+                &config,
+                &options,
+                board.render(&start),
+                |path| board.format(&start, path),
+            )?;
+        }
+        Variant::Pull => {
+            let (board, start) = level_or_exit(format, Board::parse_level_string(&level_string));
+            let board = PullBoard::new(board, rules);
+            report(
+                &board,
+                &start,
+                &config,
+                &options,
+                board.render(&start),
+                |path| board.format(&start, path),
+            )?;
+        }
+        Variant::Typed => {
+            let (board, start) =
+                level_or_exit(format, TypedBoard::parse_level_string(&level_string));
+            report(
+                &board,
+                &start,
+                &config,
+                &options,
+                board.render(&start),
+                |path| lurd::format(board.board(), start.board_state(), path),
+            )?;
+        }
+        Variant::Tiles => {
+            let (board, start) =
+                level_or_exit(format, TileBoard::parse_level_string(&level_string));
+            report(
+                &board,
+                &start,
+                &config,
+                &options,
+                board.render(&start),
+                |path| board.format(&start, path),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Unwraps a parsed level, or prints why it couldn't be parsed in `format` and exits.
+fn level_or_exit<T>(format: Format, parsed: Result<T, &'static str>) -> T {
+    match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            match format {
+                Format::Text => println!("{}", e),
+                Format::Json => {
+                    let fields = vec![("status", "invalid".into()), ("error", e.into())];
+                    println!("{}", Json::Object(fields));
+                }
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Solves a level of any variant and prints the outcome, using `format_path` to
+/// write out its moves. With `options.compare`, a solution is also compared with the
+/// cheapest one, found with A* under the same limits.
+fn report<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+    options: &SolveOptions,
+    rendered: String,
+    format_path: impl Fn(&[P::Move]) -> String,
+) -> std::io::Result<()> {
+    let format = options.format;
+    if format == Format::Text {
+        print!("{}", rendered);
+    }
+
+    let start_time = Instant::now();
+    let mut bound = None;
+    let result = match &options.mode {
+        Mode::Search => search::find_path(puzzle, start, config),
+        Mode::Resume => search::resume_path(puzzle, start, config)?,
+        Mode::External(external) => external::find_path(puzzle, start, config, external)?,
+        Mode::Frontier(frontier) => frontier::find_path(puzzle, start, config, frontier)?,
+        Mode::Anytime => {
+            let anytime = anytime::find_path(puzzle, start, config, |improvement| {
+                if format == Format::Text {
+                    println!(
+                        "\rImproved solution: {} (cost {}, at most {:.3} times the least)\x1B[0K",
+                        format_path(&improvement.path),
+                        improvement.cost,
+                        improvement.bound
+                    );
+                }
+            });
+            bound = anytime.bound;
+            SearchResult {
+                outcome: anytime.outcome,
+                stats: anytime.stats,
+            }
+        }
+        Mode::Greedy => greedy::find_path(puzzle, start, config),
+        Mode::Beam(beam) => greedy::beam_path(puzzle, start, config, beam),
+        Mode::Mcts(mcts) => mcts::find_path(puzzle, start, config, mcts),
+    };
+    let elapsed = start_time.elapsed().as_secs_f64();
+
+    let solution = result.solution().map(|path| format_path(path));
+    let cheapest = match &solution {
+        Some(_) if options.compare => {
+            let config = SearchConfig {
+                progress: false,
+                weight: 1.0,
+                checkpoint: None,
+                ..config.clone()
+            };
+            Some(search::find_path(puzzle, start, &config))
+        }
+        _ => None,
+    };
+    // the cheapest solution's cost if A* found it, otherwise the least it could be,
+    // both summed up from `Puzzle::cost` by the searches as they went
+    let least = cheapest
+        .as_ref()
+        .map(|cheapest| match cheapest.stats.solution_cost {
+            Some(cost) => (true, cost),
+            None => (false, cheapest.stats.lower_bound),
+        });
+
+    match format {
+        Format::Text => {
+            match (&result.outcome, &solution) {
+                (_, Some(lurd)) => {
+                    println!("Found solution: {}", lurd);
+                    if let Some(bound) = bound.filter(|bound| *bound > 1.0) {
+                        println!(
+                            "Solution costs at most {:.3} times the least possible.",
+                            bound
+                        );
+                    }
+                    let cost = result.stats.solution_cost.unwrap_or_default();
+                    match least {
+                        Some((true, least)) if cost >= least => println!(
+                            "Solution costs {}, {} more than the cheapest.",
+                            cost,
+                            cost - least
+                        ),
+                        Some((true, least)) => {
+                            println!("Solution costs {}, less than the {} A* found.", cost, least)
+                        }
+                        Some((false, least)) => println!(
+                            "Solution costs {}, the cheapest costs at least {}.",
+                            cost, least
+                        ),
+                        None => (),
+                    }
+                }
+                (Outcome::LimitReached(limit, partial), _) => {
+                    println!("Search stopped, {} limit reached.", limit.name());
+                    println!(
+                        "Best partial solution: {} (at least {} steps remaining)",
+                        format_path(&partial.path),
+                        partial.heuristic
+                    );
+                }
+                _ => println!("Exhausted search, level is not solvable."),
+            }
+
+            println!("Search finished after {} seconds.", elapsed);
+        }
+        Format::Json => {
+            let status = result.outcome.status();
+            let moves = solution
+                .as_ref()
+                .map(|lurd| lurd.chars().filter(char::is_ascii_alphabetic).count());
+            let pushes = solution
+                .as_ref()
+                .map(|lurd| lurd.chars().filter(char::is_ascii_uppercase).count());
+
+            let stats = &result.stats;
+            let mut fields = vec![
+                ("status", status.into()),
+                ("solution", solution.into()),
+                ("moves", moves.into()),
+                ("pushes", pushes.into()),
+                ("nodes_expanded", stats.nodes_expanded.into()),
+                ("nodes_generated", stats.nodes_generated.into()),
+                ("duplicates", stats.duplicates.into()),
+                ("dead_tile_prunes", stats.prunes.dead_tile.into()),
+                ("deadlock_prunes", stats.prunes.deadlock.into()),
+                ("other_prunes", stats.prunes.other.into()),
+                ("branching_factor", stats.branching_factor().into()),
+                ("heuristic_accuracy", stats.heuristic_accuracy().into()),
+                ("max_depth", stats.max_depth.into()),
+                ("peak_open", stats.peak_open.into()),
+                ("peak_memory", stats.peak_memory.into()),
+                ("elapsed", elapsed.into()),
+            ];
+
+            if bound.is_some() {
+                fields.push(("bound", bound.into()));
+            }
+            match least {
+                Some((true, least)) => fields.push(("optimal", least.into())),
+                Some((false, least)) => fields.push(("lower_bound", least.into())),
+                None => (),
+            }
+
+            if let Outcome::LimitReached(limit, partial) = &result.outcome {
+                fields.push(("limit", limit.name().into()));
+                fields.push(("partial", format_path(&partial.path).into()));
+                fields.push(("remaining", partial.heuristic.into()));
+            }
+
+            println!("{}", Json::Object(fields));
+        }
+    }
 
     Ok(())
 }
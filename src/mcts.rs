@@ -0,0 +1,280 @@
+use crate::rng::Rng;
+use crate::search::{
+    self, Metric, Outcome, Partial, Path, ProgressTracker, Puzzle, SearchConfig, SearchResult,
+};
+
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct MctsConfig {
+    /// Seeds the random choices, so that a search can be run again exactly.
+    pub seed: u64,
+    pub rollout: Rollout,
+    /// Most pushes a rollout makes before it's scored.
+    pub depth: u32,
+    /// How strongly the tree favours children it has visited less over children
+    /// that have scored well, as the constant in UCT.
+    pub exploration: f64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            seed: 0,
+            rollout: Rollout::Guided,
+            depth: 50,
+            exploration: std::f64::consts::SQRT_2,
+        }
+    }
+}
+
+/// How a rollout picks each push.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Rollout {
+    /// Any push, at random.
+    Random,
+    /// Of two pushes picked at random, the one the heuristic puts closer to a goal.
+    Guided,
+}
+
+/// A state in the search tree. Every state appears at most once.
+struct TreeNode<S, M> {
+    state: Rc<S>,
+    path: Rc<Path<M>>,
+    parent: Option<usize>,
+    /// `None` until the node is expanded.
+    children: Option<Vec<usize>>,
+    h: u32,
+    g: u32,
+    visits: u32,
+    reward: f64,
+    /// No goal can be reached through the node that isn't reached through another.
+    dead: bool,
+}
+
+/// Monte Carlo tree search: grows a tree from `start` one push at a time, picking
+/// where to grow it with UCT, and scores each new state by playing random pushes
+/// out from it. A rollout is cut off where the pushes `Puzzle::create_children_counted`
+/// leaves, with dead tiles and deadlocks pruned, run out. A rollout or a new state
+/// reaching a goal ends the search, so the solution found is rarely the cheapest.
+///
+/// Every state joins the tree once at most, so a level is found unsolvable once all
+/// the states in the tree are dead ends.
+pub fn find_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+    mcts: &MctsConfig,
+) -> SearchResult<P::Move> {
+    let state_bytes = 2 * size_of::<usize>() + puzzle.state_size(start);
+    let start_time = Instant::now();
+    let mut tracker = ProgressTracker::create(config, Default::default());
+    let mut rng = Rng::new(mcts.seed);
+
+    let start = Rc::new(start.clone());
+    let mut seen = HashSet::new();
+    seen.insert(start.clone());
+    let mut tree = vec![TreeNode {
+        h: puzzle.heuristic(&start),
+        state: start,
+        path: Rc::new(Path::None),
+        parent: None,
+        children: None,
+        g: 0,
+        visits: 0,
+        reward: 0.0,
+        dead: false,
+    }];
+    // nodes in the tree that haven't been expanded yet
+    let mut unexpanded = 1;
+    // the tree node closest to a goal, reported as the partial solution if a limit is hit
+    let mut best = 0;
+
+    let outcome = loop {
+        if tree[0].dead {
+            break Outcome::Unsolvable;
+        }
+        if let Some(limit) = search::check_limits(config, &tracker.stats, start_time) {
+            let partial = Partial {
+                path: search::read_path(&tree[best].path),
+                heuristic: tree[best].h,
+            };
+            break Outcome::LimitReached(limit, partial);
+        }
+
+        let leaf = select(&tree, mcts.exploration);
+        if puzzle.is_goal_state(&tree[leaf].state) {
+            tracker.stats.solution_cost = Some(tree[leaf].g);
+            break Outcome::Solved(search::read_path(&tree[leaf].path));
+        }
+
+        let node = &tree[leaf];
+        tracker.update(node.g, node.h, unexpanded, seen.len() * state_bytes);
+
+        let children = puzzle.create_children_counted(&node.state, &mut tracker.stats.prunes);
+        tracker.stats.nodes_generated += children.len() as u64;
+
+        let mut added = Vec::new();
+        for (child, actions) in children {
+            let child = Rc::new(child);
+            if !seen.insert(child.clone()) {
+                tracker.stats.duplicates += 1;
+                continue;
+            }
+
+            let node = &tree[leaf];
+            added.push(tree.len());
+            tree.push(TreeNode {
+                h: puzzle.heuristic(&child),
+                g: node.g + puzzle.cost(&actions, config.metric),
+                path: Rc::new(Path::Prev(node.path.clone(), actions)),
+                state: child,
+                parent: Some(leaf),
+                children: None,
+                visits: 0,
+                reward: 0.0,
+                dead: false,
+            });
+        }
+        unexpanded = unexpanded + added.len() - 1;
+        tree[leaf].children = Some(added.clone());
+
+        let chosen = match rng.choose(&added) {
+            Some(chosen) => *chosen,
+            None => {
+                kill(&mut tree, leaf);
+                continue;
+            }
+        };
+
+        for &child in &added {
+            if (tree[child].h, tree[child].g) < (tree[best].h, tree[best].g) {
+                best = child;
+            }
+        }
+
+        let (reward, solution) = rollout(
+            puzzle,
+            &tree[chosen],
+            tree[0].h,
+            config.metric,
+            mcts,
+            &mut rng,
+            &mut tracker,
+        );
+        if let Some((path, cost)) = solution {
+            tracker.stats.solution_cost = Some(cost);
+            break Outcome::Solved(search::read_path(&path));
+        }
+
+        let mut next = Some(chosen);
+        while let Some(node) = next {
+            tree[node].visits += 1;
+            tree[node].reward += reward;
+            next = tree[node].parent;
+        }
+    };
+
+    SearchResult {
+        outcome,
+        stats: tracker.finish(),
+    }
+}
+
+/// A path a rollout found to a goal, with what it costs.
+type Found<M> = (Rc<Path<M>>, u32);
+
+/// Walks down from the root to a node that hasn't been expanded, taking the child
+/// with the best UCT score at each step and skipping dead ones. Unvisited children
+/// come first.
+fn select<S, M>(tree: &[TreeNode<S, M>], exploration: f64) -> usize {
+    let mut node = 0;
+
+    while let Some(children) = &tree[node].children {
+        let log_visits = f64::from(tree[node].visits.max(1)).ln();
+        let score = |child: &TreeNode<S, M>| match child.visits {
+            0 => f64::INFINITY,
+            visits => {
+                let visits = f64::from(visits);
+                child.reward / visits + exploration * (log_visits / visits).sqrt()
+            }
+        };
+
+        node = children
+            .iter()
+            .copied()
+            .filter(|child| !tree[*child].dead)
+            .max_by(|a, b| score(&tree[*a]).total_cmp(&score(&tree[*b])))
+            .expect("a node with no live children is dead");
+    }
+
+    node
+}
+
+/// Marks `node` dead, along with every parent left with no live children.
+fn kill<S, M>(tree: &mut [TreeNode<S, M>], node: usize) {
+    let mut next = Some(node);
+
+    while let Some(node) = next {
+        let children = tree[node].children.as_deref().unwrap_or(&[]);
+        if !children.iter().all(|child| tree[*child].dead) {
+            break;
+        }
+
+        tree[node].dead = true;
+        next = tree[node].parent;
+    }
+}
+
+/// Plays pushes out from `node` for up to `mcts.depth` pushes. Scores between 0 and 1
+/// by how much closer to a goal than the start, at `start_h`, the rollout got, or 0
+/// if it ran into a dead end, and returns the whole path and its cost if it reached
+/// a goal.
+fn rollout<P: Puzzle>(
+    puzzle: &P,
+    node: &TreeNode<P::State, P::Move>,
+    start_h: u32,
+    metric: Metric,
+    mcts: &MctsConfig,
+    rng: &mut Rng,
+    tracker: &mut ProgressTracker,
+) -> (f64, Option<Found<P::Move>>) {
+    let mut state = (*node.state).clone();
+    let mut path = node.path.clone();
+    let mut g = node.g;
+    let mut closest = node.h;
+
+    for _ in 0..mcts.depth {
+        if puzzle.is_goal_state(&state) {
+            return (1.0, Some((path, g)));
+        }
+
+        let mut children = puzzle.create_children_counted(&state, &mut tracker.stats.prunes);
+        if children.is_empty() {
+            return (0.0, None);
+        }
+
+        let mut pick = rng.below(children.len());
+        if mcts.rollout == Rollout::Guided {
+            let other = rng.below(children.len());
+            if puzzle.heuristic(&children[other].0) < puzzle.heuristic(&children[pick].0) {
+                pick = other;
+            }
+        }
+
+        let (child, actions) = children.swap_remove(pick);
+        g += puzzle.cost(&actions, metric);
+        path = Rc::new(Path::Prev(path, actions));
+        closest = closest.min(puzzle.heuristic(&child));
+        state = child;
+    }
+
+    if puzzle.is_goal_state(&state) {
+        return (1.0, Some((path, g)));
+    }
+
+    let closer = 1.0 - f64::from(closest) / f64::from(start_h.max(1));
+    (closer.max(0.0), None)
+}
@@ -0,0 +1,262 @@
+use crate::board::{Action, Board, BoardState, Prunes};
+use crate::checkpoint::Persist;
+use crate::lurd;
+use crate::search::{Children, Metric, Puzzle};
+
+use std::io;
+
+/// A Multiban level, where several players take turns pushing the crates around
+/// and get in each other's way.
+#[derive(Clone)]
+pub struct MultiBoard {
+    board: Board,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct MultiState {
+    /// The tile each player is on.
+    players: Box<[usize]>,
+    // the crates, as seen by the first player
+    state: BoardState,
+}
+
+impl MultiState {
+    /// Approximate number of bytes this state occupies, including its buffers.
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Box<[usize]>>()
+            + std::mem::size_of_val(&*self.players)
+            + self.state.size_bytes()
+    }
+}
+
+/// A move by one of the players, numbered in the order they appear in the level.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct MultiMove {
+    pub player: usize,
+    pub action: Action,
+    /// Whether the move pushes a crate, which it does whenever there's one in the way.
+    pub push: bool,
+}
+
+impl Persist for MultiState {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.players.write(out);
+        self.state.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Ok(MultiState {
+            players: Persist::read(input)?,
+            state: BoardState::read(input)?,
+        })
+    }
+}
+
+impl Persist for MultiMove {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.player.write(out);
+        self.action.write(out);
+        self.push.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Ok(MultiMove {
+            player: usize::read(input)?,
+            action: Action::read(input)?,
+            push: bool::read(input)?,
+        })
+    }
+}
+
+impl MultiBoard {
+    /// The state as seen by `player`, with everyone else left out.
+    fn single(&self, state: &MultiState, player: usize) -> (BoardState, Vec<usize>) {
+        let others = state
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != player)
+            .map(|(_, position)| *position)
+            .collect();
+
+        let mut single = state.state.clone();
+        single.set_player(state.players[player]);
+
+        (single, others)
+    }
+
+    /// Puts the state `player` got to back among everyone else.
+    fn join(&self, state: &MultiState, player: usize, mut single: BoardState) -> MultiState {
+        let mut players = state.players.clone();
+        players[player] = self.board.player_tile(&single);
+        single.set_player(players[0]);
+
+        MultiState {
+            players,
+            state: single,
+        }
+    }
+
+    /// Where each player is in the level, in the order they appear in it.
+    pub fn players(&self, state: &MultiState) -> Vec<(u32, u32)> {
+        state
+            .players
+            .iter()
+            .map(|player| self.board.position(*player))
+            .collect()
+    }
+
+    pub fn is_goal_state(&self, state: &MultiState) -> bool {
+        self.board.is_goal_state(&state.state)
+    }
+
+    pub fn heuristic(&self, state: &MultiState) -> u32 {
+        self.board.heuristic(&state.state)
+    }
+
+    /// Generates every state reachable with a single push by any of the players. Each
+    /// child comes with the moves that reach it, listed last move first.
+    ///
+    /// A player may need to step out of another's way without pushing anything, so
+    /// when there's more than one player, walking anywhere is also a child.
+    pub fn create_children_counted(
+        &self,
+        state: &MultiState,
+        prunes: &mut Prunes,
+    ) -> Vec<(MultiState, Box<[MultiMove]>)> {
+        let mut children = Vec::new();
+
+        // the moves are listed last move first, so a push is the first of them
+        let label = |player, actions: &[Action], push: bool| -> Box<[MultiMove]> {
+            actions
+                .iter()
+                .enumerate()
+                .map(|(i, action)| MultiMove {
+                    player,
+                    action: *action,
+                    push: push && i == 0,
+                })
+                .collect()
+        };
+
+        for player in 0..state.players.len() {
+            let (single, others) = self.single(state, player);
+
+            for (child, actions) in self.board.create_children_among(&single, &others, prunes) {
+                children.push((
+                    self.join(state, player, child),
+                    label(player, &actions, true),
+                ));
+            }
+
+            if others.is_empty() {
+                continue;
+            }
+
+            for (tile, actions) in self.board.walks_among(&single, &others) {
+                let mut child = single.clone();
+                child.set_player(tile);
+                children.push((
+                    self.join(state, player, child),
+                    label(player, &actions, false),
+                ));
+            }
+        }
+
+        children
+    }
+
+    /// Applies a single move to `state`, pushing a crate if there is one in the way.
+    /// Returns the new state and whether a crate was pushed, or `None` if the move is
+    /// illegal or `step.push` doesn't match what it does.
+    pub fn step(&self, state: &MultiState, step: MultiMove) -> Option<(MultiState, bool)> {
+        let (single, others) = self.single(state, step.player);
+        let (next, push) = self.board.step_among(&single, &others, step.action)?;
+
+        if push != step.push {
+            return None;
+        }
+
+        Some((self.join(state, step.player, next), push))
+    }
+
+    /// Formats a move list as LURD, starting a new group labelled with the player's
+    /// number (from 1) whenever a different player takes over, e.g. `1:rrU 2:lL`.
+    pub fn format(&self, start: &MultiState, path: &[MultiMove]) -> String {
+        let mut groups: Vec<String> = Vec::new();
+        let mut state = start.clone();
+        let mut current = None;
+
+        for step in path {
+            let (next, push) = self.step(&state, *step).expect("illegal move in path");
+            state = next;
+
+            if current != Some(step.player) {
+                current = Some(step.player);
+                groups.push(format!("{}:", step.player + 1));
+            }
+            groups
+                .last_mut()
+                .unwrap()
+                .push(lurd::action_char(step.action, push));
+        }
+
+        groups.join(" ")
+    }
+
+    /// Draws the board in the usual level format with every player on it.
+    pub fn render(&self, state: &MultiState) -> String {
+        self.board.render_players(&state.state, &state.players)
+    }
+
+    /// Reads a level with one or more players.
+    pub fn parse_level_string(level: &str) -> Result<(Self, MultiState), &'static str> {
+        let (board, players, crates) = Board::parse_multiplayer_level_string(level)?;
+
+        Ok((
+            MultiBoard { board },
+            MultiState {
+                state: BoardState::new(players[0], crates),
+                players: players.into_boxed_slice(),
+            },
+        ))
+    }
+}
+
+impl Puzzle for MultiBoard {
+    type State = MultiState;
+    type Move = MultiMove;
+
+    fn create_children_counted(
+        &self,
+        state: &MultiState,
+        prunes: &mut Prunes,
+    ) -> Children<MultiState, MultiMove> {
+        MultiBoard::create_children_counted(self, state, prunes)
+    }
+
+    fn heuristic(&self, state: &MultiState) -> u32 {
+        MultiBoard::heuristic(self, state)
+    }
+
+    fn is_goal_state(&self, state: &MultiState) -> bool {
+        MultiBoard::is_goal_state(self, state)
+    }
+
+    fn crates_on_goals(&self, state: &MultiState) -> u32 {
+        self.board.crates_on_goals(&state.state)
+    }
+
+    fn state_size(&self, state: &MultiState) -> usize {
+        state.size_bytes()
+    }
+
+    /// Stepping out of another player's way is a child of its own, which pushes nothing.
+    fn cost(&self, moves: &[MultiMove], metric: Metric) -> u32 {
+        match metric {
+            Metric::Moves => moves.len() as u32,
+            // the crate is pushed by the last move
+            Metric::Pushes => moves.first().is_some_and(|step| step.push) as u32,
+        }
+    }
+}
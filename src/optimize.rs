@@ -0,0 +1,188 @@
+use crate::board::{Action, Board, BoardState};
+use crate::search::{self, Limits, Outcome, SearchConfig};
+
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct OptimizeConfig {
+    /// How many consecutive pushes to re-search at a time.
+    pub window: usize,
+    /// Limits for each window's search.
+    pub limits: Limits,
+}
+
+impl Default for OptimizeConfig {
+    fn default() -> Self {
+        OptimizeConfig {
+            window: 4,
+            limits: Limits {
+                nodes: Some(20_000),
+                ..Limits::default()
+            },
+        }
+    }
+}
+
+pub struct Optimized {
+    pub path: Vec<Action>,
+    /// Pushes dropped because they returned to an earlier position.
+    pub cycle_pushes: usize,
+    /// Windows that re-searching found a shorter way through.
+    pub improved_windows: usize,
+}
+
+/// A solution as the pushes it makes: the state right after each push along with
+/// the push's direction. The walks in between are left to be worked out again.
+type Pushes = Vec<(BoardState, Action)>;
+
+/// Shortens a solution without solving the level from scratch, by cutting out
+/// cycles, re-searching short windows of pushes and taking the shortest walk
+/// between each push.
+pub fn optimize(
+    board: &Board,
+    start: &BoardState,
+    path: &[Action],
+    config: &OptimizeConfig,
+) -> Result<Optimized, &'static str> {
+    let pushes = split_pushes(board, start, path)?;
+
+    let end = pushes.last().map_or(start, |(state, _)| state);
+    if !board.is_goal_state(end) {
+        return Err("Solution does not solve the level");
+    }
+
+    let original = pushes.len();
+    let pushes = remove_cycles(board, start, pushes);
+    let cycle_pushes = original - pushes.len();
+
+    let (pushes, improved_windows) = improve_windows(board, start, pushes, config);
+
+    Ok(Optimized {
+        path: join_pushes(board, start, &pushes).0,
+        cycle_pushes,
+        improved_windows,
+    })
+}
+
+fn split_pushes(
+    board: &Board,
+    start: &BoardState,
+    path: &[Action],
+) -> Result<Pushes, &'static str> {
+    let mut pushes = Vec::new();
+    let mut state = start.clone();
+
+    for action in path {
+        let (next, push) = board
+            .step(&state, *action)
+            .ok_or("Solution contains an illegal move")?;
+
+        if push {
+            pushes.push((next.clone(), *action));
+        }
+
+        state = next;
+    }
+
+    Ok(pushes)
+}
+
+/// Lays out the pushes with the shortest walk before each, returning the moves and their count.
+fn join_pushes(
+    board: &Board,
+    start: &BoardState,
+    pushes: &[(BoardState, Action)],
+) -> (Vec<Action>, usize) {
+    let mut path = Vec::new();
+    let mut state = start;
+
+    for (next, push) in pushes {
+        // the push leaves the player where the crate was, so they started one step back
+        let from = push.reverse().apply(board.player_position(next));
+        path.extend(
+            board
+                .walk(state, from)
+                .expect("push position is unreachable"),
+        );
+        path.push(*push);
+        state = next;
+    }
+
+    let moves = path.len();
+    (path, moves)
+}
+
+/// Drops every stretch of pushes that comes back around to a position it already
+/// passed through, treating positions with the player in the same region as equal.
+fn remove_cycles(board: &Board, start: &BoardState, pushes: Pushes) -> Pushes {
+    let mut kept: Pushes = Vec::new();
+    let mut keys = vec![board.normalize_player(start)];
+    let mut seen = HashMap::new();
+    seen.insert(keys[0].clone(), 0);
+
+    for (state, push) in pushes {
+        let key = board.normalize_player(&state);
+
+        match seen.get(&key) {
+            Some(&i) => {
+                for dropped in keys.drain(i + 1..) {
+                    seen.remove(&dropped);
+                }
+                kept.truncate(i);
+            }
+            None => {
+                seen.insert(key.clone(), keys.len());
+                keys.push(key);
+                kept.push((state, push));
+            }
+        }
+    }
+
+    kept
+}
+
+fn improve_windows(
+    board: &Board,
+    start: &BoardState,
+    mut pushes: Pushes,
+    config: &OptimizeConfig,
+) -> (Pushes, usize) {
+    let search_config = SearchConfig {
+        progress: false,
+        limits: config.limits.clone(),
+        ..SearchConfig::default()
+    };
+
+    let mut improved = 0;
+    let mut cost = join_pushes(board, start, &pushes).1;
+    let mut i = 0;
+
+    while i < pushes.len() {
+        let end = std::cmp::min(i + config.window.max(1), pushes.len());
+        let from = if i == 0 { start } else { &pushes[i - 1].0 };
+        let target = &pushes[end - 1].0;
+
+        if let Outcome::Solved(path) =
+            search::find_path_to(board, from, target, &search_config).outcome
+        {
+            let window = split_pushes(board, from, &path).expect("search produced an illegal move");
+
+            let mut candidate = pushes[..i].to_vec();
+            candidate.extend(window);
+            candidate.extend_from_slice(&pushes[end..]);
+
+            // the window can end with the player elsewhere in the region, which
+            // changes the next walk, so compare whole solutions
+            let candidate_cost = join_pushes(board, start, &candidate).1;
+            if candidate_cost < cost {
+                pushes = candidate;
+                cost = candidate_cost;
+                improved += 1;
+            }
+        }
+
+        i += 1;
+    }
+
+    (pushes, improved)
+}
@@ -0,0 +1,354 @@
+use crate::board::{Action, Board, BoardState, Prunes};
+use crate::checkpoint::Persist;
+use crate::lurd;
+use crate::search::{Children, Metric, Puzzle};
+
+use std::collections::VecDeque;
+use std::io;
+
+/// Which ways the player may move crates, for variants where they can pull them too.
+#[derive(Clone, Copy, Debug)]
+pub struct Rules {
+    pub pushes: bool,
+    pub pulls: bool,
+    /// What a pull costs, in moves or pushes depending on the metric. A push costs 1.
+    pub pull_cost: u32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            pushes: true,
+            pulls: true,
+            pull_cost: 1,
+        }
+    }
+}
+
+/// A player move that may drag the crate behind the player along with it.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct PullMove {
+    pub action: Action,
+    pub pull: bool,
+}
+
+impl Persist for PullMove {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.action.write(out);
+        self.pull.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Ok(PullMove {
+            action: Action::read(input)?,
+            pull: bool::read(input)?,
+        })
+    }
+}
+
+/// A level played under `Rules`. A crate can be pulled back out of a corner or off
+/// a wall, so the push-only dead tiles and freeze deadlocks of `Board` don't hold;
+/// instead a tile is only dead if a lone crate can't be moved from it to any goal.
+#[derive(Clone)]
+pub struct PullBoard {
+    board: Board,
+    rules: Rules,
+    dead_tiles: Box<[bool]>,
+    /// Number of crate moves needed to get from each tile to each goal, if it can be done at all.
+    goal_distances: Box<[Box<[u32]>]>,
+}
+
+impl PullBoard {
+    pub fn new(board: Board, rules: Rules) -> PullBoard {
+        let goal_distances: Vec<_> = board
+            .goals()
+            .iter()
+            .map(|(goal, _)| crate_distances(&board, rules, *goal))
+            .collect();
+
+        let dead_tiles = (0..board.tile_count())
+            .map(|i| goal_distances.iter().all(|dists| dists[i] == u32::MAX))
+            .collect();
+
+        PullBoard {
+            board,
+            rules,
+            dead_tiles,
+            goal_distances: goal_distances.into_boxed_slice(),
+        }
+    }
+
+    /// The cheapest a single crate move can be, which the heuristic counts crate moves in.
+    fn crate_move_cost(&self) -> u32 {
+        match (self.rules.pushes, self.rules.pulls) {
+            (true, true) => self.rules.pull_cost.min(1),
+            (false, true) => self.rules.pull_cost,
+            _ => 1,
+        }
+    }
+
+    pub fn is_goal_state(&self, state: &BoardState) -> bool {
+        self.board.is_goal_state(state)
+    }
+
+    pub fn heuristic(&self, state: &BoardState) -> u32 {
+        let board = &self.board;
+
+        let unsat_goal_dists: Vec<_> = board
+            .goals()
+            .iter()
+            .zip(self.goal_distances.iter())
+            .filter(|((goal, _), _)| !board.is_crate_at(state, *goal))
+            .map(|(_, dists)| dists)
+            .collect();
+
+        let moves: u32 = (0..board.tile_count())
+            .filter(|tile| board.is_crate_at(state, *tile) && !board.is_goal_tile(*tile))
+            .map(|tile| {
+                unsat_goal_dists
+                    .iter()
+                    .map(|dists| dists[tile])
+                    .filter(|dist| *dist != u32::MAX)
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum();
+
+        moves * self.crate_move_cost()
+    }
+
+    /// Generates every state reachable from `state` by walking and then moving a single
+    /// crate as the rules allow. Each child comes with the moves that reach it, listed
+    /// last move first.
+    pub fn create_children_counted(
+        &self,
+        state: &BoardState,
+        prunes: &mut Prunes,
+    ) -> Vec<(BoardState, Box<[PullMove]>)> {
+        let board = &self.board;
+        let is_empty = |tile: usize| !board.is_crate_at(state, tile);
+
+        let mut children = Vec::new();
+
+        let mut paths: Vec<Option<Action>> = vec![None; board.tile_count()];
+        let mut seen = vec![false; board.tile_count()];
+        let mut queue = VecDeque::new();
+
+        let read_path = |paths: &Vec<Option<Action>>, tile: usize, last| -> Box<[PullMove]> {
+            let mut path = vec![last];
+            let mut tile = tile;
+
+            while let Some(action) = paths[tile] {
+                path.push(PullMove {
+                    action,
+                    pull: false,
+                });
+                tile = board
+                    .neighbour(tile, action.reverse())
+                    .expect("walked through a wall");
+            }
+
+            path.into_boxed_slice()
+        };
+
+        queue.push_back((board.player_tile(state), None));
+
+        while let Some((tile, action)) = queue.pop_front() {
+            if seen[tile] || !is_empty(tile) {
+                continue;
+            }
+            seen[tile] = true;
+            paths[tile] = action;
+
+            for step in Action::ALL {
+                let ahead = board.neighbour(tile, step);
+
+                if let Some(ahead) = ahead.filter(|ahead| self.rules.pushes && !is_empty(*ahead)) {
+                    let target = board
+                        .neighbour(ahead, step)
+                        .filter(|target| is_empty(*target));
+
+                    match target {
+                        Some(target) if self.dead_tiles[target] => prunes.dead_tile += 1,
+                        Some(target) => {
+                            let mut child = state.clone();
+                            board.set_crate_at(&mut child, ahead, false);
+                            board.set_crate_at(&mut child, target, true);
+                            child.set_player(ahead);
+
+                            let push = PullMove {
+                                action: step,
+                                pull: false,
+                            };
+                            children.push((child, read_path(&paths, tile, push)));
+                        }
+                        None => (),
+                    }
+                }
+
+                // the crate behind the player follows them onto the tile they step off
+                let behind = board.neighbour(tile, step.reverse());
+
+                let pull = match (behind, ahead) {
+                    (Some(behind), Some(ahead)) if !is_empty(behind) && is_empty(ahead) => {
+                        Some((behind, ahead))
+                    }
+                    _ => None,
+                };
+
+                if let Some((behind, ahead)) = pull.filter(|_| self.rules.pulls) {
+                    if self.dead_tiles[tile] {
+                        prunes.dead_tile += 1;
+                    } else {
+                        let mut child = state.clone();
+                        board.set_crate_at(&mut child, behind, false);
+                        board.set_crate_at(&mut child, tile, true);
+                        child.set_player(ahead);
+
+                        let pull = PullMove {
+                            action: step,
+                            pull: true,
+                        };
+                        children.push((child, read_path(&paths, tile, pull)));
+                    }
+                }
+
+                if let Some(ahead) = ahead {
+                    queue.push_back((ahead, Some(step)));
+                }
+            }
+        }
+
+        children
+    }
+
+    /// Applies a single player move to `state`, moving a crate if it pushes or pulls one.
+    /// Returns the new state and whether a crate moved, or `None` if the move is illegal.
+    pub fn step(&self, state: &BoardState, step: PullMove) -> Option<(BoardState, bool)> {
+        let board = &self.board;
+
+        if !step.pull {
+            let (next, push) = board.step(state, step.action)?;
+            return if push && !self.rules.pushes {
+                None
+            } else {
+                Some((next, push))
+            };
+        }
+
+        let tile = board.player_tile(state);
+        let ahead = board.neighbour(tile, step.action)?;
+        let behind = board.neighbour(tile, step.action.reverse())?;
+
+        if !self.rules.pulls || board.is_crate_at(state, ahead) || !board.is_crate_at(state, behind)
+        {
+            return None;
+        }
+
+        let mut next = state.clone();
+        board.set_crate_at(&mut next, behind, false);
+        board.set_crate_at(&mut next, tile, true);
+        next.set_player(ahead);
+        Some((next, true))
+    }
+
+    /// Formats a move list as LURD, with each pull written as its uppercase letter
+    /// after a `~`, e.g. `ur~L`.
+    pub fn format(&self, start: &BoardState, path: &[PullMove]) -> String {
+        let mut out = String::new();
+        let mut state = start.clone();
+
+        for step in path {
+            let (next, moved) = self.step(&state, *step).expect("illegal move in path");
+            state = next;
+
+            if step.pull {
+                out.push('~');
+            }
+            out.push(lurd::action_char(step.action, moved));
+        }
+
+        out
+    }
+
+    /// Draws the board like `Board::render`, marking the tiles that are dead under the rules.
+    pub fn render(&self, state: &BoardState) -> String {
+        self.board.render_dead(state, &self.dead_tiles)
+    }
+}
+
+/// The fewest crate moves it takes to get a lone crate from each tile onto `goal` under
+/// `rules`, found by working back from the goal. Tiles it can't get there from are `u32::MAX`.
+fn crate_distances(board: &Board, rules: Rules, goal: usize) -> Box<[u32]> {
+    let mut dists = vec![u32::MAX; board.tile_count()];
+    let mut queue = VecDeque::new();
+
+    dists[goal] = 0;
+    queue.push_back(goal);
+
+    while let Some(to) = queue.pop_front() {
+        for step in Action::ALL {
+            // the crate moved one step in this direction to get here
+            let from = match board.neighbour(to, step.reverse()) {
+                Some(from) => from,
+                None => continue,
+            };
+
+            let pushed = rules.pushes && board.neighbour(from, step.reverse()).is_some();
+            let pulled = rules.pulls && board.neighbour(to, step).is_some();
+
+            if (pushed || pulled) && dists[from] == u32::MAX {
+                dists[from] = dists[to] + 1;
+                queue.push_back(from);
+            }
+        }
+    }
+
+    dists.into_boxed_slice()
+}
+
+impl Puzzle for PullBoard {
+    type State = BoardState;
+    type Move = PullMove;
+
+    fn create_children_counted(
+        &self,
+        state: &BoardState,
+        prunes: &mut Prunes,
+    ) -> Children<BoardState, PullMove> {
+        PullBoard::create_children_counted(self, state, prunes)
+    }
+
+    fn heuristic(&self, state: &BoardState) -> u32 {
+        PullBoard::heuristic(self, state)
+    }
+
+    fn is_goal_state(&self, state: &BoardState) -> bool {
+        PullBoard::is_goal_state(self, state)
+    }
+
+    fn crates_on_goals(&self, state: &BoardState) -> u32 {
+        self.board.crates_on_goals(state)
+    }
+
+    fn state_size(&self, state: &BoardState) -> usize {
+        state.size_bytes()
+    }
+
+    /// Walking and pushing count as usual, while a pull costs `Rules::pull_cost`.
+    fn cost(&self, moves: &[PullMove], metric: Metric) -> u32 {
+        let cost = |step: &PullMove| {
+            if step.pull {
+                self.rules.pull_cost
+            } else {
+                1
+            }
+        };
+
+        match metric {
+            Metric::Moves => moves.iter().map(cost).sum(),
+            // the crate is moved by the last move
+            Metric::Pushes => cost(&moves[0]),
+        }
+    }
+}
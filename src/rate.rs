@@ -0,0 +1,113 @@
+use crate::board::{Board, BoardState};
+use crate::json::Json;
+use crate::search::{self, SearchConfig, SearchStats};
+
+// Weights for combining solver metrics into a single difficulty score. Search
+// effort grows exponentially with difficulty, so it's counted in orders of magnitude.
+const NODES_WEIGHT: f64 = 10.0;
+const PRUNES_WEIGHT: f64 = 2.0;
+const BRANCHING_WEIGHT: f64 = 1.0;
+const PUSHES_WEIGHT: f64 = 0.5;
+const DEVIATION_WEIGHT: f64 = 20.0;
+
+pub const CSV_HEADER: &str =
+    "score,nodes_expanded,branching_factor,moves,pushes,deadlock_prunes,greedy_deviation";
+
+#[derive(Clone, Debug)]
+pub struct Rating {
+    pub nodes_expanded: u64,
+    pub branching_factor: f64,
+    pub moves: usize,
+    pub pushes: usize,
+    pub deadlock_prunes: u64,
+    /// Fraction of the solution's pushes where a different push looked closer to
+    /// solved by the heuristic, i.e. how often the player has to go against intuition.
+    pub greedy_deviation: f64,
+    pub score: f64,
+}
+
+impl Rating {
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("score", self.score.into()),
+            ("nodes_expanded", self.nodes_expanded.into()),
+            ("branching_factor", self.branching_factor.into()),
+            ("moves", self.moves.into()),
+            ("pushes", self.pushes.into()),
+            ("deadlock_prunes", self.deadlock_prunes.into()),
+            ("greedy_deviation", self.greedy_deviation.into()),
+        ])
+    }
+
+    /// The rating as a row of fields in the order of `CSV_HEADER`.
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{:.2},{},{:.3},{},{},{},{:.3}",
+            self.score,
+            self.nodes_expanded,
+            self.branching_factor,
+            self.moves,
+            self.pushes,
+            self.deadlock_prunes,
+            self.greedy_deviation
+        )
+    }
+}
+
+/// Solves the level and rates how hard it was, or returns `None` if it couldn't be solved.
+pub fn rate(board: &Board, start: &BoardState, config: &SearchConfig) -> Option<Rating> {
+    let result = search::find_path(board, start, config);
+    let path = result.solution()?;
+
+    let mut pushes = 0;
+    let mut deviations = 0;
+    let mut state = start.clone();
+    let mut last_push = start.clone();
+
+    for action in path.iter() {
+        let (next, push) = board.step(&state, *action)?;
+
+        if push {
+            pushes += 1;
+
+            let children: Vec<_> = board
+                .create_children(&last_push)
+                .into_iter()
+                .map(|(child, _)| (board.heuristic(&child), child))
+                .collect();
+            let chosen = children.iter().find(|(_, child)| *child == next)?.0;
+
+            if children.iter().any(|(h, _)| *h < chosen) {
+                deviations += 1;
+            }
+
+            last_push = next.clone();
+        }
+
+        state = next;
+    }
+
+    let greedy_deviation = if pushes == 0 {
+        0.0
+    } else {
+        deviations as f64 / pushes as f64
+    };
+
+    Some(Rating {
+        nodes_expanded: result.stats.nodes_expanded,
+        branching_factor: result.stats.branching_factor(),
+        moves: path.len(),
+        pushes,
+        deadlock_prunes: result.stats.prunes.deadlock,
+        greedy_deviation,
+        score: score(&result.stats, pushes, greedy_deviation),
+    })
+}
+
+fn score(stats: &SearchStats, pushes: usize, greedy_deviation: f64) -> f64 {
+    NODES_WEIGHT * (1.0 + stats.nodes_expanded as f64).log10()
+        + PRUNES_WEIGHT * (1.0 + stats.prunes.total() as f64).log10()
+        + BRANCHING_WEIGHT * stats.branching_factor()
+        + PUSHES_WEIGHT * pushes as f64
+        + DEVIATION_WEIGHT * greedy_deviation
+}
@@ -1,146 +1,921 @@
-use crate::board::{Action, Board, BoardState};
+use crate::board::{Action, Board, BoardState, Prunes};
+use crate::checkpoint::{self, Persist};
 
-use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::path::PathBuf;
 use std::rc::Rc;
-use std::cmp::Ordering;
-use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Child states paired with the moves reaching them, listed last move first.
+pub type Children<S, M> = Vec<(S, Box<[M]>)>;
+
+/// A puzzle the search can solve: states joined by pushes, a goal to reach and a
+/// heuristic estimate of how far away it is.
+pub trait Puzzle {
+    type State: Clone + Eq + Hash + Persist;
+    type Move: Copy + Persist;
+
+    /// Every state reachable with a single push, each with the moves that reach it
+    /// listed last move first. Pushes that are ruled out are counted into `prunes`.
+    fn create_children_counted(
+        &self,
+        state: &Self::State,
+        prunes: &mut Prunes,
+    ) -> Children<Self::State, Self::Move>;
+
+    /// A lower bound on the number of pushes left to solve `state`.
+    fn heuristic(&self, state: &Self::State) -> u32;
+
+    fn is_goal_state(&self, state: &Self::State) -> bool;
+
+    /// How many crates are already where they need to be, for `TieBreak::MoreOnGoals`.
+    fn crates_on_goals(&self, state: &Self::State) -> u32;
+
+    /// Approximate number of bytes `state` occupies, for estimating memory use.
+    fn state_size(&self, state: &Self::State) -> usize;
+
+    /// What reaching a child with `moves` costs under `metric`. Every move counts
+    /// once, and every child is one push.
+    fn cost(&self, moves: &[Self::Move], metric: Metric) -> u32 {
+        match metric {
+            Metric::Moves => moves.len() as u32,
+            Metric::Pushes => 1,
+        }
+    }
+}
+
+impl Puzzle for Board {
+    type State = BoardState;
+    type Move = Action;
+
+    fn create_children_counted(
+        &self,
+        state: &BoardState,
+        prunes: &mut Prunes,
+    ) -> Vec<(BoardState, Box<[Action]>)> {
+        Board::create_children_counted(self, state, prunes)
+    }
+
+    fn heuristic(&self, state: &BoardState) -> u32 {
+        Board::heuristic(self, state)
+    }
+
+    fn is_goal_state(&self, state: &BoardState) -> bool {
+        Board::is_goal_state(self, state)
+    }
+
+    fn crates_on_goals(&self, state: &BoardState) -> u32 {
+        Board::crates_on_goals(self, state)
+    }
 
-struct ProgressTracker {
-    frequency: u32,
-    max_seen_depth: u32,
-    max_seen_f: u32,
-    counter: u32,
+    fn state_size(&self, state: &BoardState) -> usize {
+        state.size_bytes()
+    }
+}
+
+/// Options controlling how `find_path` runs.
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    /// Print a running progress line to stdout while searching.
+    pub progress: bool,
+    /// How many states are expanded between each progress line and call to `observer`.
+    pub progress_interval: u64,
+    /// Called with the statistics so far as the progress line is, and once more when
+    /// the search finishes, so that a caller can show progress its own way.
+    pub observer: Option<Observer>,
+    pub metric: Metric,
+    /// Multiplies the heuristic, as in weighted A*. Above 1 the search heads for the
+    /// goal more eagerly, finding solutions that cost at most `weight` times the least.
+    pub weight: f64,
+    pub tie_break: TieBreak,
+    pub limits: Limits,
+    pub cancel: CancelToken,
+    pub checkpoint: Option<CheckpointConfig>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            progress: true,
+            // visually appealing - not obvious it's skipping numbers
+            progress_interval: 1237,
+            observer: None,
+            metric: Metric::Moves,
+            weight: 1.0,
+            tie_break: TieBreak::LowerH,
+            limits: Limits::default(),
+            cancel: CancelToken::new(),
+            checkpoint: None,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// The f-value the open list is ordered by, with the heuristic weighted.
+    pub(crate) fn f(&self, g: u32, h: u32) -> u32 {
+        // the cast saturates, so a huge weight just puts the state last
+        g.saturating_add((f64::from(h) * self.weight).ceil() as u32)
+    }
+}
+
+/// A callback that's shown a search's statistics as it runs.
+#[derive(Clone)]
+pub struct Observer(Arc<dyn Fn(&SearchStats) + Send + Sync>);
+
+impl Observer {
+    pub fn new(observe: impl Fn(&SearchStats) + Send + Sync + 'static) -> Self {
+        Observer(Arc::new(observe))
+    }
+}
+
+impl std::fmt::Debug for Observer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Observer")
+    }
+}
+
+/// Where and how often a search saves its progress, so that `resume_path` can carry
+/// it on after the process is stopped. A search that hits a limit saves before it
+/// returns as well.
+#[derive(Clone, Debug)]
+pub struct CheckpointConfig {
+    pub path: PathBuf,
+    pub interval: Duration,
+}
+
+/// What the search minimises. The heuristic counts pushes, so it's a lower bound on both.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Metric {
+    Moves,
+    Pushes,
+}
+
+/// Which of the states with the lowest f-value the search expands first. States that
+/// tie on this too are expanded in the order they were reached, except with `Lifo`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TieBreak {
+    /// The state reached first.
+    Fifo,
+    /// The state reached last, which dives deeper like a depth-first search.
+    Lifo,
+    /// The state closest to a goal, which with equal f-values is also the deepest.
+    LowerH,
+    /// The state with the most crates already on goals.
+    MoreOnGoals,
+}
+
+impl TieBreak {
+    /// Ranks `state` among the states with the same f-value, lowest first.
+    pub(crate) fn rank<P: Puzzle>(self, puzzle: &P, state: &P::State, h: u32) -> u32 {
+        match self {
+            TieBreak::Fifo | TieBreak::Lifo => 0,
+            TieBreak::LowerH => h,
+            TieBreak::MoreOnGoals => u32::MAX - puzzle.crates_on_goals(state),
+        }
+    }
+
+    /// Orders states that tie on rank as well, given how many were reached before.
+    pub(crate) fn order(self, reached: u64) -> u64 {
+        match self {
+            TieBreak::Lifo => u64::MAX - reached,
+            _ => reached,
+        }
+    }
+}
+
+/// Resource limits after which the search gives up. `None` means unlimited.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+    /// Bytes, compared against the same estimate reported as `SearchStats::peak_memory`.
+    pub memory: Option<usize>,
+}
+
+/// A handle that can stop a running search from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken(Arc::new(AtomicBool::new(false)))
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(AtomicOrdering::Relaxed)
+    }
+}
+
+/// The reason a search stopped before reaching an answer.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Limit {
+    Time,
+    Nodes,
+    Memory,
+    Cancelled,
+    /// Beam search dropped states and ran out of the ones it kept.
+    Width,
+}
+
+impl Limit {
+    pub fn name(self) -> &'static str {
+        match self {
+            Limit::Time => "time",
+            Limit::Nodes => "nodes",
+            Limit::Memory => "memory",
+            Limit::Cancelled => "cancelled",
+            Limit::Width => "width",
+        }
+    }
+}
+
+/// The furthest the search got before stopping: the expanded state closest
+/// to a goal according to the heuristic, and the path that reaches it.
+#[derive(Clone, Debug)]
+pub struct Partial<M = Action> {
+    pub path: Vec<M>,
+    pub heuristic: u32,
+}
+
+#[derive(Clone, Debug)]
+pub enum Outcome<M = Action> {
+    Solved(Vec<M>),
+    Unsolvable,
+    LimitReached(Limit, Partial<M>),
+}
+
+impl<M> Outcome<M> {
+    /// Short machine-readable name for the outcome, as used in JSON and CSV output.
+    pub fn status(&self) -> &'static str {
+        match self {
+            Outcome::Solved(_) => "solved",
+            Outcome::Unsolvable => "unsolvable",
+            Outcome::LimitReached(Limit::Time, _) => "timeout",
+            Outcome::LimitReached(..) => "limit_reached",
+        }
+    }
+}
+
+/// Counters collected over the course of a search.
+#[derive(Clone, Debug, Default)]
+pub struct SearchStats {
+    pub nodes_expanded: u64,
+    /// Children pushed onto the open list, including ones later found to be duplicates.
+    pub nodes_generated: u64,
+    /// Children dropped, or taken off the open list and skipped, for being states
+    /// already reached.
+    pub duplicates: u64,
+    pub prunes: Prunes,
+    pub max_depth: u32,
+    /// The largest f-value seen, which bounds the solution length from below.
+    pub lower_bound: u32,
+    /// Estimated peak size in bytes of the open list, seen set and path tree.
+    pub peak_memory: usize,
+    /// Most states waiting to be expanded at once.
+    pub peak_open: usize,
+    /// The heuristic's estimate for the start.
+    pub start_heuristic: u32,
+    /// What the solution found costs, once there is one.
+    pub solution_cost: Option<u32>,
+}
+
+impl SearchStats {
+    /// Average number of children each expanded node added to the open list.
+    pub fn branching_factor(&self) -> f64 {
+        if self.nodes_expanded == 0 {
+            0.0
+        } else {
+            self.nodes_generated as f64 / self.nodes_expanded as f64
+        }
+    }
+
+    /// How much of the solution's cost the heuristic saw coming from the start, from 0
+    /// to 1 for a heuristic that never overestimates. `None` without a solution.
+    pub fn heuristic_accuracy(&self) -> Option<f64> {
+        self.solution_cost.map(|cost| match cost {
+            0 => 1.0,
+            cost => f64::from(self.start_heuristic) / f64::from(cost),
+        })
+    }
+}
+
+pub struct SearchResult<M = Action> {
+    pub outcome: Outcome<M>,
+    pub stats: SearchStats,
+}
+
+impl<M> SearchResult<M> {
+    pub fn solution(&self) -> Option<&Vec<M>> {
+        match &self.outcome {
+            Outcome::Solved(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) struct ProgressTracker {
+    frequency: u64,
+    verbose: bool,
+    observer: Option<Observer>,
+    pub(crate) stats: SearchStats,
 }
 
 impl ProgressTracker {
-    fn update(&mut self, depth: u32, h: u32) {
-        self.counter += 1;
+    /// Counts a state expanded at `depth` with heuristic `h`, with `open` states still
+    /// waiting and an estimated `memory` in use.
+    pub(crate) fn update(&mut self, depth: u32, h: u32, open: usize, memory: usize) {
+        // the first state expanded is always the start
+        if self.stats.nodes_expanded == 0 {
+            self.stats.start_heuristic = h;
+        }
+        self.stats.nodes_expanded += 1;
 
-        self.max_seen_depth = std::cmp::max(self.max_seen_depth, depth);
-        self.max_seen_f = std::cmp::max(self.max_seen_f, depth + h);
+        self.stats.max_depth = std::cmp::max(self.stats.max_depth, depth);
+        self.stats.lower_bound = std::cmp::max(self.stats.lower_bound, depth + h);
+        self.stats.peak_memory = std::cmp::max(self.stats.peak_memory, memory);
+        self.stats.peak_open = std::cmp::max(self.stats.peak_open, open);
 
-        if self.counter % self.frequency == 0 {
-            self.print_progress();
-            std::io::stdout().flush().unwrap();
+        if self.stats.nodes_expanded.is_multiple_of(self.frequency) {
+            if self.verbose {
+                self.print_progress();
+                std::io::stdout().flush().unwrap();
+            }
+            if let Some(Observer(observe)) = &self.observer {
+                observe(&self.stats);
+            }
         }
     }
 
     fn print_progress(&self) {
         print!(
             "\rSearched {} states, to a max depth of {}, solution is at least {} steps.\x1B[0K",
-            self.counter, self.max_seen_depth, self.max_seen_f
+            self.stats.nodes_expanded, self.stats.max_depth, self.stats.lower_bound
         );
     }
 
-    fn finish(&self) {
-        self.print_progress();
-        println!();
+    pub(crate) fn finish(self) -> SearchStats {
+        if self.verbose {
+            self.print_progress();
+            println!();
+        }
+        if let Some(Observer(observe)) = &self.observer {
+            observe(&self.stats);
+        }
+
+        self.stats
     }
 
-    fn create(frequency: u32) -> Self {
+    pub(crate) fn create(config: &SearchConfig, stats: SearchStats) -> Self {
         let pt = ProgressTracker {
-            frequency: frequency,
-            max_seen_depth: 0,
-            max_seen_f: 0,
-            counter: 0,
+            frequency: config.progress_interval.max(1),
+            verbose: config.progress,
+            observer: config.observer.clone(),
+            stats,
         };
 
-        pt.print_progress();
+        if pt.verbose {
+            pt.print_progress();
+        }
 
         pt
     }
 }
 
-#[derive(PartialEq, Eq)]
-enum Path {
+// rough per-entry costs used to estimate how much memory the search is holding on to,
+// Rc allocations hold two counts alongside their contents
+fn path_size<M>(actions: usize) -> usize {
+    2 * size_of::<usize>() + size_of::<Path<M>>() + actions * size_of::<M>()
+}
+
+pub(crate) type Best<M> = (u32, u32, Rc<Path<M>>);
+
+pub(crate) enum Path<M> {
     None,
-    Prev(Rc<Path>, Box<[Action]>),
+    Prev(Rc<Path<M>>, Box<[M]>),
+}
+
+#[derive(Clone)]
+pub(crate) struct Node<S, M> {
+    pub(crate) state: Rc<S>,
+    pub(crate) path: Rc<Path<M>>,
+    pub(crate) h: u32,
+    pub(crate) g: u32,
+    // from `SearchConfig::f`, which is `h + g` unless the heuristic is weighted
+    pub(crate) f: u32,
+    // from `TieBreak`, and unique to the node so that no two nodes compare equal
+    pub(crate) rank: u32,
+    pub(crate) order: u64,
 }
 
-#[derive(Eq)]
-struct Node {
-    state: Rc<BoardState>,
-    path: Rc<Path>,
-    h: u32,
-    g: u32,
+impl<S, M> Node<S, M> {
+    pub(crate) fn key(&self) -> (u32, u32, u64) {
+        (self.f, self.rank, self.order)
+    }
 }
 
-impl Ord for Node {
+impl<S, M> Eq for Node<S, M> {}
+
+impl<S, M> Ord for Node<S, M> {
     fn cmp(&self, other: &Self) -> Ordering {
         // swapped for min heap
-        (other.h + other.g).cmp(&(self.h + self.g))
+        other.key().cmp(&self.key())
     }
 }
 
-impl PartialOrd for Node {
+impl<S, M> PartialOrd for Node<S, M> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl PartialEq for Node {
+impl<S, M> PartialEq for Node<S, M> {
     fn eq(&self, other: &Self) -> bool {
-        self.h + self.g == other.h + other.g
+        self.key() == other.key()
     }
 }
 
-pub fn find_path(board: &Board, start: &BoardState) -> Option<Vec<Action>> {
+pub fn find_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+) -> SearchResult<P::Move> {
+    search(
+        puzzle,
+        start,
+        config,
+        Frontier::new(puzzle, start, puzzle.heuristic(start), config),
+        |state| puzzle.is_goal_state(state),
+        |state| puzzle.heuristic(state),
+    )
+}
+
+/// Carries on a search that `find_path` saved to `config.checkpoint`, going on exactly
+/// as it would have done had it not been stopped. Fails if there's no checkpoint to
+/// read, or it was saved for a different level or with different search options.
+pub fn resume_path<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+) -> io::Result<SearchResult<P::Move>> {
+    let checkpoint = config.checkpoint.as_ref().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "No checkpoint to resume from")
+    })?;
+    let frontier = Frontier::load(&std::fs::read(&checkpoint.path)?, start, config)?;
+
+    Ok(search(
+        puzzle,
+        start,
+        config,
+        frontier,
+        |state| puzzle.is_goal_state(state),
+        |state| puzzle.heuristic(state),
+    ))
+}
+
+/// Finds the shortest path from `start` to a state with the same crates as `target`
+/// and the player somewhere they could walk to `target`'s position from.
+pub fn find_path_to(
+    board: &Board,
+    start: &BoardState,
+    target: &BoardState,
+    config: &SearchConfig,
+) -> SearchResult {
+    let target = board.normalize_player(target);
+    // every crate out of place needs at least one more push
+    let heuristic = |state: &BoardState| board.misplaced_crates(state, &target);
+
+    search(
+        board,
+        start,
+        config,
+        Frontier::new(board, start, heuristic(start), config),
+        |state| state.same_crates(&target) && board.normalize_player(state) == target,
+        heuristic,
+    )
+}
+
+// identifies a checkpoint file, and the version of its layout
+const CHECKPOINT_MAGIC: &[u8] = b"pushcrate checkpoint 6\n";
+
+/// Everything the search loop works on, which is what a checkpoint saves.
+struct Frontier<S, M> {
     // Use a HashMap so we can use the Entry API - hopefully won't need to in a future version of Rust
-    let mut seen: HashMap<Rc<BoardState>, ()> = HashMap::new();
-    let mut heap: BinaryHeap<Node> = BinaryHeap::new();
+    seen: HashMap<Rc<S>, ()>,
+    heap: BinaryHeap<Node<S, M>>,
+    // lowest (h, g) node seen so far, reported as the partial solution if a limit is hit
+    best: Option<Best<M>>,
+    path_bytes: usize,
+    // nodes pushed so far, which orders ties
+    reached: u64,
+    stats: SearchStats,
+}
 
-    {
+impl<S: Clone + Eq + Hash + Persist, M: Copy + Persist> Frontier<S, M> {
+    fn new<P: Puzzle<State = S>>(puzzle: &P, start: &S, h: u32, config: &SearchConfig) -> Self {
+        let mut heap = BinaryHeap::new();
         heap.push(Node {
             state: Rc::new(start.clone()),
             path: Rc::new(Path::None),
-            h: 0, // don't really need heuristic for start node
+            // needed for the start node so it can be reported as partial progress
+            h,
             g: 0,
+            f: config.f(0, h),
+            rank: config.tie_break.rank(puzzle, start, h),
+            order: config.tie_break.order(0),
         });
+
+        Frontier {
+            seen: HashMap::new(),
+            heap,
+            best: None,
+            path_bytes: path_size::<M>(0),
+            reached: 1,
+            stats: SearchStats::default(),
+        }
+    }
+
+    /// Writes the frontier out in the layout `load` reads. The path tree is flattened
+    /// with every path after the one it extends, and the heap keeps its order, so a
+    /// resumed search breaks ties the same way.
+    fn save(&self, start: &S, config: &SearchConfig) -> Vec<u8> {
+        let mut out = CHECKPOINT_MAGIC.to_vec();
+        (config.metric as u8).write(&mut out);
+        (config.tie_break as u8).write(&mut out);
+        config.weight.to_bits().write(&mut out);
+        start.write(&mut out);
+
+        let stats = &self.stats;
+        stats.nodes_expanded.write(&mut out);
+        stats.nodes_generated.write(&mut out);
+        stats.duplicates.write(&mut out);
+        stats.prunes.dead_tile.write(&mut out);
+        stats.prunes.deadlock.write(&mut out);
+        stats.prunes.other.write(&mut out);
+        stats.max_depth.write(&mut out);
+        stats.lower_bound.write(&mut out);
+        stats.peak_memory.write(&mut out);
+        stats.peak_open.write(&mut out);
+        stats.start_heuristic.write(&mut out);
+        self.path_bytes.write(&mut out);
+        self.reached.write(&mut out);
+
+        // path 0 is the empty path, and every other one is numbered from 1 as it's written
+        let mut ids: HashMap<*const Path<M>, usize> = HashMap::new();
+        let mut paths = Vec::new();
+
+        let mut id_of = |path: &Rc<Path<M>>| -> usize {
+            let mut unwritten = vec![];
+            let mut next = path;
+
+            while let Path::Prev(prev, _) = next.as_ref() {
+                if ids.contains_key(&Rc::as_ptr(next)) {
+                    break;
+                }
+                unwritten.push(next);
+                next = prev;
+            }
+
+            for path in unwritten.into_iter().rev() {
+                if let Path::Prev(prev, actions) = path.as_ref() {
+                    let parent = ids.get(&Rc::as_ptr(prev)).copied().unwrap_or(0);
+                    parent.write(&mut paths);
+                    actions.write(&mut paths);
+                    ids.insert(Rc::as_ptr(path), ids.len() + 1);
+                }
+            }
+
+            ids.get(&Rc::as_ptr(path)).copied().unwrap_or(0)
+        };
+
+        let mut nodes = Vec::new();
+        for node in self.heap.iter() {
+            node.state.write(&mut nodes);
+            id_of(&node.path).write(&mut nodes);
+            node.h.write(&mut nodes);
+            node.g.write(&mut nodes);
+            node.f.write(&mut nodes);
+            node.rank.write(&mut nodes);
+            node.order.write(&mut nodes);
+        }
+
+        let best = self
+            .best
+            .as_ref()
+            .map(|(h, g, path)| ((*h, *g), id_of(path)));
+
+        ids.len().write(&mut out);
+        out.extend(paths);
+        self.heap.len().write(&mut out);
+        out.extend(nodes);
+        self.seen.len().write(&mut out);
+        self.seen.keys().for_each(|state| state.write(&mut out));
+        best.write(&mut out);
+
+        out
+    }
+
+    fn load(bytes: &[u8], start: &S, config: &SearchConfig) -> io::Result<Self> {
+        let mut input = bytes
+            .strip_prefix(CHECKPOINT_MAGIC)
+            .ok_or_else(|| checkpoint::invalid("Not a checkpoint file"))?;
+        let input = &mut input;
+
+        if u8::read(input)? != config.metric as u8 {
+            return Err(checkpoint::invalid(
+                "Checkpoint was saved with a different metric",
+            ));
+        }
+        if u8::read(input)? != config.tie_break as u8 {
+            return Err(checkpoint::invalid(
+                "Checkpoint was saved with a different tie-break",
+            ));
+        }
+        if u64::read(input)? != config.weight.to_bits() {
+            return Err(checkpoint::invalid(
+                "Checkpoint was saved with a different weight",
+            ));
+        }
+        if S::read(input)? != *start {
+            return Err(checkpoint::invalid(
+                "Checkpoint was saved for a different level",
+            ));
+        }
+
+        let stats = SearchStats {
+            nodes_expanded: u64::read(input)?,
+            nodes_generated: u64::read(input)?,
+            duplicates: u64::read(input)?,
+            prunes: Prunes {
+                dead_tile: u64::read(input)?,
+                deadlock: u64::read(input)?,
+                other: u64::read(input)?,
+            },
+            max_depth: u32::read(input)?,
+            lower_bound: u32::read(input)?,
+            peak_memory: usize::read(input)?,
+            peak_open: usize::read(input)?,
+            start_heuristic: u32::read(input)?,
+            solution_cost: None,
+        };
+        let path_bytes = usize::read(input)?;
+        let reached = u64::read(input)?;
+
+        let mut paths = vec![Rc::new(Path::None)];
+        for _ in 0..usize::read(input)? {
+            let parent = paths
+                .get(usize::read(input)?)
+                .cloned()
+                .ok_or_else(|| checkpoint::invalid("Checkpoint path extends a missing path"))?;
+            paths.push(Rc::new(Path::Prev(parent, Persist::read(input)?)));
+        }
+
+        let path = |id: usize| {
+            paths
+                .get(id)
+                .cloned()
+                .ok_or_else(|| checkpoint::invalid("Checkpoint refers to a missing path"))
+        };
+
+        let mut heap = Vec::new();
+        for _ in 0..usize::read(input)? {
+            heap.push(Node {
+                state: Rc::new(S::read(input)?),
+                path: path(usize::read(input)?)?,
+                h: u32::read(input)?,
+                g: u32::read(input)?,
+                f: u32::read(input)?,
+                rank: u32::read(input)?,
+                order: u64::read(input)?,
+            });
+        }
+
+        let mut seen = HashMap::new();
+        for _ in 0..usize::read(input)? {
+            seen.insert(Rc::new(S::read(input)?), ());
+        }
+
+        let best = match Option::<((u32, u32), usize)>::read(input)? {
+            Some(((h, g), id)) => Some((h, g, path(id)?)),
+            None => None,
+        };
+
+        Ok(Frontier {
+            seen,
+            // the saved order is already a heap, so this leaves it as it was
+            heap: BinaryHeap::from(heap),
+            best,
+            path_bytes,
+            reached,
+            stats,
+        })
     }
+}
+
+/// Saves `frontier` to the checkpoint file, by way of a temporary file so that a
+/// process stopped while saving leaves the last checkpoint whole.
+fn save_checkpoint<S: Clone + Eq + Hash + Persist, M: Copy + Persist>(
+    checkpoint: &CheckpointConfig,
+    frontier: &Frontier<S, M>,
+    start: &S,
+    config: &SearchConfig,
+) {
+    let mut temporary = checkpoint.path.clone().into_os_string();
+    temporary.push(".tmp");
 
-    // frequency is visually appealing - not obvious it's skipping numbers
-    let mut tracker = ProgressTracker::create(1237);
+    let saved = std::fs::write(&temporary, frontier.save(start, config))
+        .and_then(|_| std::fs::rename(&temporary, &checkpoint.path));
+
+    // losing a checkpoint isn't worth stopping a long search over
+    if let Err(e) = saved {
+        eprintln!(
+            "Couldn't save checkpoint to {}: {}",
+            checkpoint.path.display(),
+            e
+        );
+    }
+}
+
+fn search<P: Puzzle>(
+    puzzle: &P,
+    start: &P::State,
+    config: &SearchConfig,
+    frontier: Frontier<P::State, P::Move>,
+    is_goal: impl Fn(&P::State) -> bool,
+    heuristic: impl Fn(&P::State) -> u32,
+) -> SearchResult<P::Move> {
+    let Frontier {
+        mut seen,
+        mut heap,
+        mut best,
+        mut path_bytes,
+        mut reached,
+        stats,
+    } = frontier;
+
+    // every state is the same size, and the path tree only ever grows
+    let state_bytes = 2 * size_of::<usize>() + puzzle.state_size(start);
+    let entry_bytes = size_of::<(Rc<P::State>, ())>() + 1;
+
+    let start_time = Instant::now();
+    let mut last_checkpoint = Instant::now();
+
+    let mut tracker = ProgressTracker::create(config, stats);
 
     loop {
-        match heap.pop() {
+        if let Some(checkpoint) = &config.checkpoint {
+            if last_checkpoint.elapsed() >= checkpoint.interval {
+                let frontier = Frontier {
+                    seen,
+                    heap,
+                    best,
+                    path_bytes,
+                    reached,
+                    stats: tracker.stats.clone(),
+                };
+                save_checkpoint(checkpoint, &frontier, start, config);
+
+                seen = frontier.seen;
+                heap = frontier.heap;
+                best = frontier.best;
+                last_checkpoint = Instant::now();
+            }
+        }
+
+        // left on the heap until it's expanded, so a checkpoint saved at a limit has
+        // the heap exactly as it was, and a resumed search breaks ties the same way
+        match heap.peek().cloned() {
             None => {
-                tracker.finish();
-                return None;
+                return SearchResult {
+                    outcome: Outcome::Unsolvable,
+                    stats: tracker.finish(),
+                };
             }
             Some(node) => {
                 match seen.entry(node.state.clone()) {
-                    Entry::Occupied(_) => continue,
+                    Entry::Occupied(_) => {
+                        heap.pop();
+                        tracker.stats.duplicates += 1;
+                        continue;
+                    }
                     Entry::Vacant(entry) => {
                         entry.insert(());
-                    },
+                    }
                 }
 
                 let state = &node.state;
 
-                tracker.update(node.g, node.h);
+                let memory = seen.capacity() * entry_bytes
+                    + heap.capacity() * size_of::<Node<P::State, P::Move>>()
+                    + (seen.len() + heap.len()) * state_bytes
+                    + path_bytes;
+                tracker.update(node.g, node.h, heap.len(), memory);
 
-                if board.is_goal_state(&state) {
-                    tracker.finish();
-                    return Some(read_path(&node.path));
+                if is_goal(state) {
+                    tracker.stats.solution_cost = Some(node.g);
+                    return SearchResult {
+                        outcome: Outcome::Solved(read_path(&node.path)),
+                        stats: tracker.finish(),
+                    };
                 }
 
-                for (child, actions) in board.create_children(&state) {
-                    let h = board.heuristic(&child);
-                    let g = node.g + actions.len() as u32;
+                if best
+                    .as_ref()
+                    .is_none_or(|(h, g, _)| (node.h, node.g) < (*h, *g))
+                {
+                    best = Some((node.h, node.g, node.path.clone()));
+                }
+
+                if let Some(limit) = check_limits(config, &tracker.stats, start_time) {
+                    let (heuristic, _, path) = best.clone().unwrap();
+                    let partial = Partial {
+                        path: read_path(&path),
+                        heuristic,
+                    };
+
+                    if let Some(checkpoint) = &config.checkpoint {
+                        // a resumed search expands this node again, so it isn't counted here
+                        seen.remove(&node.state);
+                        let mut stats = tracker.stats.clone();
+                        stats.nodes_expanded -= 1;
+
+                        let frontier = Frontier {
+                            seen,
+                            heap,
+                            best,
+                            path_bytes,
+                            reached,
+                            stats,
+                        };
+                        save_checkpoint(checkpoint, &frontier, start, config);
+                    }
+
+                    return SearchResult {
+                        outcome: Outcome::LimitReached(limit, partial),
+                        stats: tracker.finish(),
+                    };
+                }
+
+                heap.pop();
+                let children = puzzle.create_children_counted(state, &mut tracker.stats.prunes);
+                tracker.stats.nodes_generated += children.len() as u64;
+
+                for (child, actions) in children {
+                    let h = heuristic(&child);
+                    let g = node.g + puzzle.cost(&actions, config.metric);
+                    path_bytes += path_size::<P::Move>(actions.len());
                     heap.push(Node {
+                        f: config.f(g, h),
+                        rank: config.tie_break.rank(puzzle, &child, h),
+                        order: config.tie_break.order(reached),
                         state: Rc::new(child),
                         path: Rc::new(Path::Prev(node.path.clone(), actions)),
-                        h: h,
-                        g: g,
+                        h,
+                        g,
                     });
+                    reached += 1;
                 }
             }
         }
     }
 }
 
-fn read_path(end_state: &Rc<Path>) -> Vec<Action> {
+pub(crate) fn check_limits(
+    config: &SearchConfig,
+    stats: &SearchStats,
+    start_time: Instant,
+) -> Option<Limit> {
+    let limits = &config.limits;
+
+    if config.cancel.is_cancelled() {
+        Some(Limit::Cancelled)
+    } else if limits
+        .nodes
+        .is_some_and(|nodes| stats.nodes_expanded >= nodes)
+    {
+        Some(Limit::Nodes)
+    } else if limits
+        .memory
+        .is_some_and(|memory| stats.peak_memory >= memory)
+    {
+        Some(Limit::Memory)
+    } else if limits.time.is_some_and(|time| start_time.elapsed() >= time) {
+        Some(Limit::Time)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn read_path<M: Copy>(end_state: &Rc<Path<M>>) -> Vec<M> {
     let mut path = vec![];
     let mut state = end_state.as_ref();
 
@@ -0,0 +1,539 @@
+use crate::board::{Action, Board, BoardState, Prunes};
+use crate::checkpoint::{self, Persist};
+use crate::lurd;
+use crate::search::{Children, Puzzle};
+
+use std::collections::VecDeque;
+use std::io;
+
+/// A floor tile with a mechanic of its own. A new mechanic needs a character in
+/// `from_char` and `to_char`, and its effect on players and crates moving onto it in
+/// `TileBoard::enter_player` and `TileBoard::enter_crate`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Tile {
+    Floor,
+    /// Swallows the first crate pushed into it and is plain floor from then on. The
+    /// player can't step into it while it's open.
+    Hole,
+    /// Can only be entered moving in its direction.
+    OneWay(Action),
+    /// A crate pushed onto ice slides on until something stops it.
+    Ice,
+    /// Sends whatever enters it to the other tile with the same number, where it stays
+    /// put. A player pushing a crate off a teleport steps onto it without being sent.
+    Teleport(u8),
+}
+
+impl Tile {
+    pub fn from_char(c: char) -> Option<Tile> {
+        match c {
+            'o' => Some(Tile::Hole),
+            '^' => Some(Tile::OneWay(Action::Up)),
+            'v' => Some(Tile::OneWay(Action::Down)),
+            '<' => Some(Tile::OneWay(Action::Left)),
+            '>' => Some(Tile::OneWay(Action::Right)),
+            '=' => Some(Tile::Ice),
+            '1'..='9' => Some(Tile::Teleport(c as u8 - b'0')),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Floor => ' ',
+            Tile::Hole => 'o',
+            Tile::OneWay(Action::Up) => '^',
+            Tile::OneWay(Action::Down) => 'v',
+            Tile::OneWay(Action::Left) => '<',
+            Tile::OneWay(Action::Right) => '>',
+            Tile::Ice => '=',
+            Tile::Teleport(n) => (b'0' + n) as char,
+        }
+    }
+}
+
+/// Where a crate pushed onto a tile ends up.
+enum Landing {
+    At(usize),
+    /// Fell into the hole with this number.
+    Swallowed(usize),
+    Blocked,
+}
+
+/// A level with `Tile` mechanics laid over the usual walls, crates and goals. Tiles
+/// are numbered as the board numbers them.
+#[derive(Clone)]
+pub struct TileBoard {
+    board: Board,
+    tiles: Box<[Tile]>,
+    /// The other end of the teleport on each tile.
+    partners: Box<[Option<usize>]>,
+    /// The number of the hole on each tile, indexing `TileState::filled`.
+    holes: Box<[Option<usize>]>,
+    /// Tiles a crate can't be moved from onto any goal, even with other crates stopping
+    /// it on ice.
+    dead_tiles: Box<[bool]>,
+    /// Number of pushes needed to get a crate from each tile to each goal, if it can be done at all.
+    goal_distances: Box<[Box<[u32]>]>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct TileState {
+    state: BoardState,
+    /// Which holes have swallowed a crate, by number.
+    filled: Box<[bool]>,
+}
+
+impl TileState {
+    /// Approximate number of bytes this state occupies, including its buffers.
+    pub fn size_bytes(&self) -> usize {
+        self.state.size_bytes()
+            + std::mem::size_of::<Box<[bool]>>()
+            + std::mem::size_of_val(&*self.filled)
+    }
+
+    /// The state with the holes left out, which is all that the crates and player are.
+    pub fn board_state(&self) -> &BoardState {
+        &self.state
+    }
+}
+
+impl Persist for TileState {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.state.write(out);
+        checkpoint::write_bits(&self.filled, out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Ok(TileState {
+            state: BoardState::read(input)?,
+            filled: checkpoint::read_bits(input)?,
+        })
+    }
+}
+
+impl TileBoard {
+    fn open_hole(&self, state: &TileState, tile: usize) -> Option<usize> {
+        self.holes[tile].filter(|hole| !state.filled[*hole])
+    }
+
+    fn is_crate(&self, state: &TileState, tile: usize) -> bool {
+        self.board.is_crate_at(&state.state, tile)
+    }
+
+    /// Where the player ends up stepping from `from`, or `None` if they can't.
+    fn enter_player(&self, state: &TileState, from: usize, action: Action) -> Option<usize> {
+        let to = self.board.neighbour(from, action)?;
+
+        if self.is_crate(state, to) || self.open_hole(state, to).is_some() {
+            return None;
+        }
+
+        match self.tiles[to] {
+            Tile::OneWay(direction) if direction != action => None,
+            Tile::Teleport(_) => self.partners[to].filter(|to| !self.is_crate(state, *to)),
+            _ => Some(to),
+        }
+    }
+
+    /// Where the crate on `from` ends up when pushed.
+    fn enter_crate(&self, state: &TileState, from: usize, action: Action) -> Landing {
+        let mut tile = from;
+
+        // a crate that has slid at least one tile stops where it is instead of being blocked
+        let stop = |tile| {
+            if tile == from {
+                Landing::Blocked
+            } else {
+                Landing::At(tile)
+            }
+        };
+
+        loop {
+            let to = match self.board.neighbour(tile, action) {
+                Some(to) if !self.is_crate(state, to) => to,
+                _ => return stop(tile),
+            };
+
+            if let Some(hole) = self.open_hole(state, to) {
+                return Landing::Swallowed(hole);
+            }
+
+            match self.tiles[to] {
+                Tile::OneWay(direction) if direction != action => return stop(tile),
+                Tile::Teleport(_) => {
+                    return match self.partners[to] {
+                        Some(partner) if !self.is_crate(state, partner) => Landing::At(partner),
+                        _ => stop(tile),
+                    }
+                }
+                Tile::Ice => tile = to,
+                _ => return Landing::At(to),
+            }
+        }
+    }
+
+    /// Pushes the crate in front of the player at `from`, returning the new state or
+    /// `None` if there's no crate there or it can't move.
+    fn push(&self, state: &TileState, from: usize, action: Action) -> Option<TileState> {
+        let at = self.board.neighbour(from, action)?;
+
+        if !self.is_crate(state, at) {
+            return None;
+        }
+        if let Tile::OneWay(direction) = self.tiles[at] {
+            if direction != action {
+                return None;
+            }
+        }
+
+        let mut next = state.clone();
+        self.board.set_crate_at(&mut next.state, at, false);
+        next.state.set_player(at);
+
+        match self.enter_crate(state, at, action) {
+            Landing::At(to) => self.board.set_crate_at(&mut next.state, to, true),
+            Landing::Swallowed(hole) => next.filled[hole] = true,
+            Landing::Blocked => return None,
+        }
+
+        Some(next)
+    }
+
+    /// Whether more crates are stuck on dead tiles than there are crates to spare.
+    fn is_deadlocked(&self, state: &TileState) -> bool {
+        let mut crates = 0;
+        let mut stuck = 0;
+
+        for tile in (0..self.tiles.len()).filter(|tile| self.is_crate(state, *tile)) {
+            crates += 1;
+            stuck += self.dead_tiles[tile] as usize;
+        }
+
+        stuck > crates - self.board.goals().len()
+    }
+
+    pub fn is_goal_state(&self, state: &TileState) -> bool {
+        self.board.is_goal_state(&state.state)
+    }
+
+    pub fn heuristic(&self, state: &TileState) -> u32 {
+        let crates: Vec<_> = (0..self.tiles.len())
+            .filter(|tile| self.is_crate(state, *tile))
+            .collect();
+
+        // every free goal needs a crate of its own, which has to come from at least as
+        // far away as the nearest one. With spare crates about, the nearest crate to
+        // each goal is all that can be counted on
+        self.board
+            .goals()
+            .iter()
+            .zip(self.goal_distances.iter())
+            .filter(|((goal, _), _)| !self.is_crate(state, *goal))
+            .map(|(_, dists)| {
+                crates
+                    .iter()
+                    .map(|i| dists[*i])
+                    .filter(|dist| *dist != u32::MAX)
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// Generates every state reachable from `state` with a single push. Each child comes
+    /// with the moves that reach it, listed last move first.
+    pub fn create_children_counted(
+        &self,
+        state: &TileState,
+        prunes: &mut Prunes,
+    ) -> Vec<(TileState, Box<[Action]>)> {
+        let mut children = Vec::new();
+
+        // teleports mean a step can't be undone just by reversing it, so each tile
+        // remembers the tile it was reached from
+        let mut came_from: Vec<Option<(usize, Action)>> = vec![None; self.tiles.len()];
+        let mut seen = vec![false; self.tiles.len()];
+        let mut queue = VecDeque::new();
+
+        let read_path = |came_from: &Vec<Option<(usize, Action)>>, tile, push| -> Box<[Action]> {
+            let mut path = vec![push];
+            let mut tile = tile;
+
+            while let Some((previous, action)) = came_from[tile] {
+                path.push(action);
+                tile = previous;
+            }
+
+            path.into_boxed_slice()
+        };
+
+        let start = self.board.player_tile(&state.state);
+        seen[start] = true;
+        queue.push_back(start);
+
+        while let Some(tile) = queue.pop_front() {
+            for action in Action::ALL {
+                if let Some(child) = self.push(state, tile, action) {
+                    if self.is_deadlocked(&child) {
+                        prunes.other += 1;
+                    } else {
+                        children.push((child, read_path(&came_from, tile, action)));
+                    }
+                }
+
+                if let Some(next) = self.enter_player(state, tile, action) {
+                    if !seen[next] {
+                        seen[next] = true;
+                        came_from[next] = Some((tile, action));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        children
+    }
+
+    /// Applies a single player move to `state`, pushing a crate if there is one in the way.
+    /// Returns the new state and whether a crate was pushed, or `None` if the move is illegal.
+    pub fn step(&self, state: &TileState, action: Action) -> Option<(TileState, bool)> {
+        let player = self.board.player_tile(&state.state);
+
+        if let Some(to) = self.enter_player(state, player, action) {
+            let mut next = state.clone();
+            next.state.set_player(to);
+            return Some((next, false));
+        }
+
+        self.push(state, player, action).map(|next| (next, true))
+    }
+
+    /// Formats a move list in LURD notation, replaying it to find the pushes.
+    pub fn format(&self, start: &TileState, path: &[Action]) -> String {
+        let mut state = start.clone();
+
+        path.iter()
+            .map(|action| {
+                let (next, push) = self.step(&state, *action).expect("illegal move in path");
+                state = next;
+                lurd::action_char(*action, push)
+            })
+            .collect()
+    }
+
+    /// Draws the board like `Board::render`, with the tiles' own characters on any
+    /// tile that's otherwise empty. Filled holes are drawn as floor.
+    pub fn render(&self, state: &TileState) -> String {
+        let mut drawn: Vec<char> = self
+            .board
+            .render_dead(&state.state, &self.dead_tiles)
+            .chars()
+            .collect();
+        // each row of the drawing ends in a newline
+        let width = self.board.width() + 1;
+
+        for (i, tile) in self.tiles.iter().enumerate() {
+            let (x, y) = self.board.position(i);
+            let at = y as usize * width + x as usize;
+            let filled = self.holes[i].is_some_and(|hole| state.filled[hole]);
+
+            if *tile != Tile::Floor && !filled && (drawn[at] == ' ' || drawn[at] == '-') {
+                drawn[at] = tile.to_char();
+            }
+        }
+
+        drawn.into_iter().collect()
+    }
+
+    /// Reads a level in the usual format, extended with `Tile` characters: `o` for a
+    /// hole, `^ v < >` for one-way floors, `=` for ice and a digit for each end of a
+    /// pair of teleports. A level needs a crate for every goal and every hole.
+    pub fn parse_level_string(level: &str) -> Result<(Self, TileState), &'static str> {
+        // lines are picked out the same way the board does, so positions line up
+        let lines: Vec<&str> = level
+            .split('\n')
+            .map(|s| s.trim_end())
+            .skip_while(|s| s.is_empty())
+            .take_while(|s| !s.is_empty())
+            .collect();
+
+        let mut plain = String::new();
+        let mut placed = Vec::new();
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                match Tile::from_char(c) {
+                    Some(tile) => {
+                        placed.push(((x as u32, y as u32), tile));
+                        plain.push(' ');
+                    }
+                    None => plain.push(c),
+                }
+            }
+            plain.push('\n');
+        }
+
+        let count = |chars: &str| level.chars().filter(|c| chars.contains(*c)).count();
+        let holes = placed
+            .iter()
+            .filter(|(_, tile)| *tile == Tile::Hole)
+            .count();
+
+        if count("$*bB") != count(".*+PB") + holes {
+            return Err("Number of crates is not the number of goals plus holes");
+        }
+
+        // teleports can lead to places the player couldn't walk to, so every tile with
+        // a mechanic counts as part of the level
+        let entrances: Vec<_> = placed.iter().map(|(position, _)| *position).collect();
+        let (board, state) = Board::parse_with_spare_crates(&plain, holes, &entrances)?;
+
+        let mut tiles = vec![Tile::Floor; board.tile_count()];
+        let mut hole_numbers = vec![None; board.tile_count()];
+        let mut holes = 0;
+
+        for (position, tile) in placed.iter() {
+            let i = board.tile(*position).expect("tile is outside the level");
+            tiles[i] = *tile;
+
+            if *tile == Tile::Hole {
+                hole_numbers[i] = Some(holes);
+                holes += 1;
+            }
+        }
+
+        let mut partners = vec![None; tiles.len()];
+        for n in 1..=9 {
+            let ends: Vec<_> = (0..tiles.len())
+                .filter(|i| tiles[*i] == Tile::Teleport(n))
+                .collect();
+
+            match ends[..] {
+                [] => (),
+                [a, b] => {
+                    partners[a] = Some(b);
+                    partners[b] = Some(a);
+                }
+                _ => return Err("Teleports must come in pairs"),
+            }
+        }
+
+        let mut tile_board = TileBoard {
+            board,
+            tiles: tiles.into_boxed_slice(),
+            partners: partners.into_boxed_slice(),
+            holes: hole_numbers.into_boxed_slice(),
+            dead_tiles: Box::default(),
+            goal_distances: Box::default(),
+        };
+
+        tile_board.goal_distances = tile_board
+            .board
+            .goals()
+            .iter()
+            .map(|(goal, _)| tile_board.crate_distances(*goal))
+            .collect();
+
+        tile_board.dead_tiles = (0..tile_board.tiles.len())
+            .map(|i| {
+                tile_board
+                    .goal_distances
+                    .iter()
+                    .all(|dists| dists[i] == u32::MAX)
+            })
+            .collect();
+
+        Ok((
+            tile_board,
+            TileState {
+                state,
+                filled: vec![false; holes].into_boxed_slice(),
+            },
+        ))
+    }
+
+    /// The fewest pushes it takes to get a crate from each tile onto `goal`, with holes
+    /// taken as already filled and other crates there to stop it on any ice it slides
+    /// over, so it's never more than it really takes. Tiles it can't get there from are
+    /// `u32::MAX`.
+    fn crate_distances(&self, goal: usize) -> Box<[u32]> {
+        let empty = TileState {
+            state: BoardState::new(goal, vec![false; self.tiles.len()].into_boxed_slice()),
+            filled: vec![true; self.holes.iter().flatten().count()].into_boxed_slice(),
+        };
+
+        // every push a crate can make, to work back along from the goal
+        let mut pushed_from = vec![Vec::new(); self.tiles.len()];
+
+        for from in 0..self.tiles.len() {
+            for action in Action::ALL {
+                // the player has to have somewhere to push from
+                if self.board.neighbour(from, action.reverse()).is_none() {
+                    continue;
+                }
+
+                if let Landing::At(to) = self.enter_crate(&empty, from, action) {
+                    pushed_from[to].push(from);
+                }
+
+                // a crate in the way can stop a sliding crate on any ice it crosses
+                let mut tile = from;
+                while let Some(to) = self
+                    .board
+                    .neighbour(tile, action)
+                    .filter(|to| self.tiles[*to] == Tile::Ice)
+                {
+                    pushed_from[to].push(from);
+                    tile = to;
+                }
+            }
+        }
+
+        let mut dists = vec![u32::MAX; self.tiles.len()];
+        let mut queue = VecDeque::new();
+
+        dists[goal] = 0;
+        queue.push_back(goal);
+
+        while let Some(to) = queue.pop_front() {
+            for from in pushed_from[to].iter() {
+                if dists[*from] == u32::MAX {
+                    dists[*from] = dists[to] + 1;
+                    queue.push_back(*from);
+                }
+            }
+        }
+
+        dists.into_boxed_slice()
+    }
+}
+
+impl Puzzle for TileBoard {
+    type State = TileState;
+    type Move = Action;
+
+    fn create_children_counted(
+        &self,
+        state: &TileState,
+        prunes: &mut Prunes,
+    ) -> Children<TileState, Action> {
+        TileBoard::create_children_counted(self, state, prunes)
+    }
+
+    fn heuristic(&self, state: &TileState) -> u32 {
+        TileBoard::heuristic(self, state)
+    }
+
+    fn is_goal_state(&self, state: &TileState) -> bool {
+        TileBoard::is_goal_state(self, state)
+    }
+
+    fn crates_on_goals(&self, state: &TileState) -> u32 {
+        self.board.crates_on_goals(&state.state)
+    }
+
+    fn state_size(&self, state: &TileState) -> usize {
+        state.size_bytes()
+    }
+}
@@ -0,0 +1,45 @@
+use crate::grid::{Direction, GridBoard, Topology};
+
+/// Triangular cells, one per character, pointing up where the column and row add
+/// up to an even number and down elsewhere:
+///
+/// ```text
+/// #######
+/// #@ $ .#
+/// #######
+/// ```
+///
+/// Each triangle only shares an edge with three others: the two either side of it in
+/// its row, and the one below an up triangle or above a down one. The six directions
+/// pair up on those edges. From an up triangle, West and NorthWest both step through
+/// its left edge, East and NorthEast through its right one, and the two southern
+/// directions through its bottom; a down triangle pairs West with SouthWest, East with
+/// SouthEast and the two northern directions at its top. A push carries on along the
+/// strip of triangles it started in, alternating between up and down pointing cells.
+#[derive(Clone, Copy, Debug)]
+pub struct Tri;
+
+pub type TriBoard = GridBoard<Tri>;
+
+impl Topology for Tri {
+    fn step((x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let up = (x + y) % 2 == 0;
+
+        match (direction, up) {
+            (Direction::West, _) | (Direction::SouthWest, false) | (Direction::NorthWest, true) => {
+                Some((x.checked_sub(1)?, y))
+            }
+            (Direction::East, _) | (Direction::SouthEast, false) | (Direction::NorthEast, true) => {
+                Some((x + 1, y))
+            }
+            (Direction::SouthWest, true) | (Direction::SouthEast, true) => Some((x, y + 1)),
+            (Direction::NorthWest, false) | (Direction::NorthEast, false) => {
+                Some((x, y.checked_sub(1)?))
+            }
+        }
+    }
+
+    fn is_cell(_: (usize, usize), _: (usize, usize)) -> bool {
+        true
+    }
+}
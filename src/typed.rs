@@ -0,0 +1,315 @@
+use crate::board::{Action, Board, BoardState, Prunes};
+use crate::checkpoint::Persist;
+use crate::search::{Children, Puzzle};
+
+use std::io;
+
+/// A level where crates and goals come in colours, and a crate only counts as
+/// placed on a goal of its own colour.
+#[derive(Clone)]
+pub struct TypedBoard {
+    board: Board,
+    /// The colour of the goal on each tile, with 0 for plain goals.
+    goal_colours: Box<[Option<u8>]>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct TypedState {
+    state: BoardState,
+    /// The colour of the crate on each tile, with 0 for plain crates and empty tiles.
+    colours: Box<[u8]>,
+}
+
+impl TypedState {
+    /// Approximate number of bytes this state occupies, including its buffers.
+    pub fn size_bytes(&self) -> usize {
+        self.state.size_bytes()
+            + std::mem::size_of::<Box<[u8]>>()
+            + std::mem::size_of_val(&*self.colours)
+    }
+
+    /// The state with the colours left out, which is all that moving around depends on.
+    pub fn board_state(&self) -> &BoardState {
+        &self.state
+    }
+}
+
+impl Persist for TypedState {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.state.write(out);
+        self.colours.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> io::Result<Self> {
+        Ok(TypedState {
+            state: BoardState::read(input)?,
+            colours: Persist::read(input)?,
+        })
+    }
+}
+
+/// The colour a letter stands for and whether it's a goal, or `None` if it's not a
+/// colour. Lowercase letters are crates and uppercase letters goals, apart from the
+/// letters the usual level format already uses for players and crates.
+fn colour(c: char) -> Option<(u8, bool)> {
+    if !c.is_ascii_alphabetic() || "bBpP".contains(c) {
+        return None;
+    }
+
+    Some((
+        c.to_ascii_lowercase() as u8 - b'a' + 1,
+        c.is_ascii_uppercase(),
+    ))
+}
+
+fn colour_char(colour: u8, goal: bool) -> char {
+    let c = (b'a' + colour - 1) as char;
+    if goal {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    }
+}
+
+impl TypedBoard {
+    /// The board with the colours left out, which is all that moving around depends on.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    #[inline]
+    fn index(&self, position: (u32, u32)) -> usize {
+        self.board
+            .tile(position)
+            .expect("position is outside the level")
+    }
+
+    /// Moves the colour along with a crate `action` pushed onto `next`'s player tile.
+    fn move_colour(&self, colours: &mut [u8], next: &BoardState, action: Action) {
+        let from = self.board.player_tile(next);
+        let to = self
+            .board
+            .neighbour(from, action)
+            .expect("crate pushed into a wall");
+
+        colours[to] = colours[from];
+        colours[from] = 0;
+    }
+
+    /// Whether a crate on tile `i` would be on a goal of its own colour.
+    #[inline]
+    fn is_placed(&self, state: &TypedState, i: usize) -> bool {
+        self.goal_colours[i] == Some(state.colours[i])
+    }
+
+    pub fn is_goal_state(&self, state: &TypedState) -> bool {
+        self.board
+            .crate_tiles(&state.state)
+            .all(|i| self.is_placed(state, i))
+    }
+
+    /// Number of crates on a goal of their own colour.
+    pub fn crates_on_goals(&self, state: &TypedState) -> u32 {
+        self.board
+            .crate_tiles(&state.state)
+            .filter(|i| self.is_placed(state, *i))
+            .count() as u32
+    }
+
+    pub fn heuristic(&self, state: &TypedState) -> u32 {
+        let crates: Vec<_> = self
+            .board
+            .crate_tiles(&state.state)
+            .filter(|i| !self.is_placed(state, *i))
+            .collect();
+
+        let unsat_goal_dists: Vec<_> = self
+            .board
+            .goals()
+            .iter()
+            .map(|(goal, dists)| (*goal, dists))
+            .filter(|(i, _)| {
+                !self.board.is_crate_at(&state.state, *i) || !self.is_placed(state, *i)
+            })
+            .map(|(i, dists)| (self.goal_colours[i], dists))
+            .collect();
+
+        // as with plain crates, each one has to be moved at least as far as the
+        // nearest free goal, only now it has to be a goal of its own colour
+        crates
+            .into_iter()
+            .map(|i| {
+                unsat_goal_dists
+                    .iter()
+                    .filter(|(colour, _)| *colour == Some(state.colours[i]))
+                    .map(|(_, dists)| dists[i])
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// Generates every state reachable from `state` with a single push, carrying each
+    /// crate's colour along with it. Each child comes with the moves that reach it,
+    /// listed last move first.
+    pub fn create_children_counted(
+        &self,
+        state: &TypedState,
+        prunes: &mut Prunes,
+    ) -> Vec<(TypedState, Box<[Action]>)> {
+        self.board
+            .create_children_counted(&state.state, prunes)
+            .into_iter()
+            .map(|(child, actions)| {
+                let mut colours = state.colours.clone();
+                self.move_colour(&mut colours, &child, actions[0]);
+
+                (
+                    TypedState {
+                        state: child,
+                        colours,
+                    },
+                    actions,
+                )
+            })
+            .collect()
+    }
+
+    /// Applies a single player move to `state`, pushing a crate if there is one in the way.
+    /// Returns the new state and whether a crate was pushed, or `None` if the move is illegal.
+    pub fn step(&self, state: &TypedState, action: Action) -> Option<(TypedState, bool)> {
+        let (next, push) = self.board.step(&state.state, action)?;
+
+        let mut colours = state.colours.clone();
+        if push {
+            self.move_colour(&mut colours, &next, action);
+        }
+
+        Some((
+            TypedState {
+                state: next,
+                colours,
+            },
+            push,
+        ))
+    }
+
+    /// Draws the board like `Board::render`, with coloured crates and the coloured
+    /// goals they haven't covered written as their letters.
+    pub fn render(&self, state: &TypedState) -> String {
+        let mut tiles: Vec<char> = self.board.render(&state.state).chars().collect();
+        // each row of the drawing ends in a newline
+        let width = self.board.width() + 1;
+
+        for (i, colour) in self.goal_colours.iter().enumerate() {
+            let (x, y) = self.board.position(i);
+            let tile = y as usize * width + x as usize;
+
+            if state.colours[i] != 0 {
+                tiles[tile] = colour_char(state.colours[i], false);
+            } else if let (Some(colour @ 1..), '.') = (colour, tiles[tile]) {
+                tiles[tile] = colour_char(*colour, true);
+            }
+        }
+
+        tiles.into_iter().collect()
+    }
+
+    /// Reads a level in the usual format, extended with a lowercase letter for a crate
+    /// of that colour and an uppercase letter for a goal of that colour. `b` and `p` are
+    /// taken already, so there are 24 colours; plain crates and goals match each other.
+    pub fn parse_level_string(level: &str) -> Result<(Self, TypedState), &'static str> {
+        // lines are picked out the same way the board does, so positions line up
+        let lines: Vec<&str> = level
+            .split('\n')
+            .map(|s| s.trim_end())
+            .skip_while(|s| s.is_empty())
+            .take_while(|s| !s.is_empty())
+            .collect();
+
+        let mut plain = String::new();
+        let mut crates = Vec::new();
+        let mut goals = Vec::new();
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                match colour(c) {
+                    Some((colour, true)) => {
+                        goals.push(((x as u32, y as u32), colour));
+                        plain.push('.');
+                    }
+                    Some((colour, false)) => {
+                        crates.push(((x as u32, y as u32), colour));
+                        plain.push('$');
+                    }
+                    None => plain.push(c),
+                }
+            }
+            plain.push('\n');
+        }
+
+        let (board, state) = Board::parse_level_string(&plain)?;
+
+        let count =
+            |list: &[((u32, u32), u8)], colour| list.iter().filter(|(_, c)| *c == colour).count();
+        if (1..=26).any(|colour| count(&crates, colour) != count(&goals, colour)) {
+            return Err("Number of crates and number of goals of a colour are not the same");
+        }
+
+        let tiles = board.tile_count();
+        let mut typed = TypedBoard {
+            goal_colours: vec![None; tiles].into_boxed_slice(),
+            board,
+        };
+
+        for (goal, _) in typed.board.goals().to_vec() {
+            typed.goal_colours[goal] = Some(0);
+        }
+        for (position, colour) in goals {
+            let i = typed.index(position);
+            typed.goal_colours[i] = Some(colour);
+        }
+
+        let mut colours = vec![0; tiles];
+        for (position, colour) in crates {
+            colours[typed.index(position)] = colour;
+        }
+
+        Ok((
+            typed,
+            TypedState {
+                state,
+                colours: colours.into_boxed_slice(),
+            },
+        ))
+    }
+}
+
+impl Puzzle for TypedBoard {
+    type State = TypedState;
+    type Move = Action;
+
+    fn create_children_counted(
+        &self,
+        state: &TypedState,
+        prunes: &mut Prunes,
+    ) -> Children<TypedState, Action> {
+        TypedBoard::create_children_counted(self, state, prunes)
+    }
+
+    fn heuristic(&self, state: &TypedState) -> u32 {
+        TypedBoard::heuristic(self, state)
+    }
+
+    fn is_goal_state(&self, state: &TypedState) -> bool {
+        TypedBoard::is_goal_state(self, state)
+    }
+
+    fn crates_on_goals(&self, state: &TypedState) -> u32 {
+        TypedBoard::crates_on_goals(self, state)
+    }
+
+    fn state_size(&self, state: &TypedState) -> usize {
+        state.size_bytes()
+    }
+}
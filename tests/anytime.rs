@@ -0,0 +1,94 @@
+mod common;
+
+use common::{parse, replay};
+use pushcrate::anytime;
+use pushcrate::search::{self, Limits, Metric, SearchConfig};
+
+const LEVELS: [&str; 4] = ["simplest.txt", "demo01.txt", "demo02.txt", "demo03.txt"];
+
+#[test]
+fn weighted_search_stays_within_its_weight_of_the_cheapest() {
+    for metric in [Metric::Moves, Metric::Pushes] {
+        let optimal = SearchConfig {
+            progress: false,
+            metric,
+            ..SearchConfig::default()
+        };
+        let weighted = SearchConfig {
+            weight: 2.0,
+            ..optimal.clone()
+        };
+
+        for name in LEVELS {
+            let (board, start) = parse(name);
+            let a_star = search::find_path(&board, &start, &optimal);
+            let expected = replay(&board, &start, a_star.solution().unwrap(), metric).1;
+
+            let found = search::find_path(&board, &start, &weighted);
+            let path = found
+                .solution()
+                .unwrap_or_else(|| panic!("{} was not solved", name));
+            let (state, cost) = replay(&board, &start, path, metric);
+
+            assert!(board.is_goal_state(&state), "{}", name);
+            assert!(cost <= 2 * expected, "{} {:?}", name, metric);
+        }
+    }
+}
+
+#[test]
+fn anytime_search_improves_until_it_finds_the_cheapest() {
+    for metric in [Metric::Moves, Metric::Pushes] {
+        let optimal = SearchConfig {
+            progress: false,
+            metric,
+            ..SearchConfig::default()
+        };
+        let weighted = SearchConfig {
+            weight: 5.0,
+            ..optimal.clone()
+        };
+
+        for name in LEVELS {
+            let (board, start) = parse(name);
+            let a_star = search::find_path(&board, &start, &optimal);
+            let expected = replay(&board, &start, a_star.solution().unwrap(), metric).1;
+
+            let mut costs = Vec::new();
+            let result = anytime::find_path(&board, &start, &weighted, |improvement| {
+                assert!(improvement.bound >= 1.0, "{}", name);
+                costs.push(improvement.cost as usize);
+            });
+
+            assert!(costs.windows(2).all(|pair| pair[1] < pair[0]), "{}", name);
+            assert_eq!(costs.last(), Some(&expected), "{} {:?}", name, metric);
+            assert_eq!(result.bound, Some(1.0), "{}", name);
+
+            let (state, cost) = replay(&board, &start, result.solution().unwrap(), metric);
+            assert!(board.is_goal_state(&state), "{}", name);
+            assert_eq!(cost, expected, "{} {:?}", name, metric);
+        }
+    }
+}
+
+#[test]
+fn anytime_search_keeps_its_best_solution_when_stopped() {
+    let (board, start) = parse("demo02.txt");
+    let config = SearchConfig {
+        progress: false,
+        weight: 5.0,
+        limits: Limits {
+            nodes: Some(30),
+            ..Limits::default()
+        },
+        ..SearchConfig::default()
+    };
+
+    let mut found = None;
+    let result = anytime::find_path(&board, &start, &config, |improvement| {
+        found = Some(improvement.path.clone());
+    });
+
+    assert_eq!(result.solution(), found.as_ref());
+    assert!(result.bound.unwrap() > 1.0);
+}
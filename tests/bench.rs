@@ -0,0 +1,85 @@
+use pushcrate::bench::{self, BenchResult};
+
+fn result(name: &str, status: &str, solution: Option<(usize, usize)>, nodes: u64) -> BenchResult {
+    BenchResult {
+        name: name.to_string(),
+        status: status.to_string(),
+        moves: solution.map(|(moves, _)| moves),
+        pushes: solution.map(|(_, pushes)| pushes),
+        nodes,
+        time: 0.25,
+    }
+}
+
+#[test]
+fn collections_split_on_blank_lines() {
+    let levels = bench::split_collection("; First\n#####\n#@$.#\n#####\n\n\n#####\n#.$@#\n#####\n");
+
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[0].name, "First");
+    assert_eq!(levels[0].text, "#####\n#@$.#\n#####");
+    assert_eq!(levels[1].name, "2");
+    assert_eq!(levels[1].text, "#####\n#.$@#\n#####");
+}
+
+#[test]
+fn csv_round_trips() {
+    let results = vec![
+        result("demo01.txt", "solved", Some((10, 4)), 16),
+        result("a, b", "limit_reached", None, 1000),
+        result("unsolvable", "unsolvable", None, 3),
+    ];
+
+    let csv = bench::to_csv(&results);
+    assert_eq!(bench::parse_csv(&csv), Ok(results));
+}
+
+#[test]
+fn csv_without_header_is_rejected() {
+    assert!(bench::parse_csv("demo01.txt,solved,10,4,16,0.25\n").is_err());
+
+    let header = "level,status,moves,pushes,nodes,time\n";
+    assert!(bench::parse_csv(&format!("{}demo01.txt,solved,ten,4,16,0.25\n", header)).is_err());
+    assert!(bench::parse_csv(&format!("{}solved,10,4,16\n", header)).is_err());
+}
+
+#[test]
+fn unchanged_results_are_not_regressions() {
+    let baseline = vec![
+        result("solved", "solved", Some((10, 4)), 100),
+        result("unsolved", "limit_reached", None, 1000),
+    ];
+    let results = vec![
+        result("solved", "solved", Some((10, 4)), 105),
+        result("unsolved", "limit_reached", None, 5000),
+        result("new", "solved", Some((8, 2)), 10),
+    ];
+
+    assert!(bench::compare(&baseline, &results).is_empty());
+}
+
+#[test]
+fn worse_results_are_regressions() {
+    let baseline = vec![
+        result("lost", "solved", Some((10, 4)), 100),
+        result("longer", "solved", Some((10, 4)), 100),
+        result("slower", "solved", Some((10, 4)), 100),
+        result("missing", "solved", Some((10, 4)), 100),
+    ];
+    let results = vec![
+        result("lost", "limit_reached", None, 100),
+        result("longer", "solved", Some((12, 5)), 100),
+        result("slower", "solved", Some((10, 4)), 111),
+    ];
+
+    assert_eq!(
+        bench::compare(&baseline, &results),
+        vec![
+            "lost: no longer solved (limit_reached)",
+            "longer: solution grew from 10 to 12 moves",
+            "longer: solution grew from 4 to 5 pushes",
+            "slower: expanded 111 nodes, up from 100",
+            "missing: missing from the results",
+        ]
+    );
+}
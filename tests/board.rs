@@ -0,0 +1,72 @@
+mod common;
+
+use common::parse;
+use pushcrate::board::{Action, Board, BoardState};
+
+/// Plays back a move list stored last move first, as returned by `create_children`.
+fn replay_reversed(board: &Board, start: &BoardState, actions: &[Action]) -> BoardState {
+    actions.iter().rev().fold(start.clone(), |state, action| {
+        board.step(&state, *action).expect("illegal move").0
+    })
+}
+
+#[test]
+fn parents_undo_children() {
+    let (board, start) = parse("demo01.txt");
+
+    for (child, _) in board.create_children(&start) {
+        let parents = board.create_parents(&child);
+
+        // every parent must push forward into the child
+        for (parent, actions) in parents.iter() {
+            assert_eq!(&replay_reversed(&board, parent, actions), &child);
+        }
+
+        // and one of them has the crates where they started
+        assert!(parents
+            .iter()
+            .any(|(parent, _)| board.iter_crates(parent).eq(board.iter_crates(&start))));
+    }
+}
+
+#[test]
+fn parents_of_unknown_player_state() {
+    let (board, start) = parse("simplest.txt");
+    let (solved, _) = board.step(&start, Action::Right).unwrap();
+
+    let parents = board.create_parents_any_player(&solved);
+    assert_eq!(parents.len(), 1);
+    assert_eq!(parents[0].0, start);
+    assert_eq!(&*parents[0].1, &[Action::Right]);
+}
+
+#[test]
+fn crates_are_pulled_off_goals_against_walls() {
+    let (board, solved) = Board::parse_level_string("######\n#@  *#\n######\n").unwrap();
+
+    let parents = board.create_parents(&solved);
+    assert_eq!(parents.len(), 1);
+    assert_eq!(
+        board.iter_crates(&parents[0].0).collect::<Vec<_>>(),
+        [(3, 1)]
+    );
+    assert_eq!(board.player_position(&parents[0].0), (2, 1));
+}
+
+#[test]
+fn irregular_levels_round_trip() {
+    let level = "    #####\n    #  .#\n#####$  #\n#@      #\n#########\n";
+    let (board, start) = Board::parse_level_string(level).unwrap();
+
+    assert_eq!(board.level_string(&start), level);
+}
+
+#[test]
+fn crates_the_player_cant_reach_are_rejected() {
+    let level = "#####\n#@ .#\n#####\n#$  #\n#####\n";
+
+    match Board::parse_level_string(level) {
+        Ok(_) => panic!("parsing succeeded"),
+        Err(e) => assert_eq!(e, "Level has crates or goals the player can't reach"),
+    }
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::parse;
+use pushcrate::board::{Board, BoardState};
+use pushcrate::certificate::{self, Certificate, Proof};
+use pushcrate::search::SearchConfig;
+
+fn prove(board: &Board, start: &BoardState) -> Proof {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    certificate::prove_unsolvable(board, start, &config)
+}
+
+#[test]
+fn unsolvable_levels_have_certificates_that_check() {
+    for name in ["unsolvable/1.txt", "unsolvable/2.txt", "unsolvable/3.txt"] {
+        let (board, start) = parse(name);
+
+        let certificate = match prove(&board, &start) {
+            Proof::Unsolvable(certificate) => certificate,
+            _ => panic!("{} was not proven unsolvable", name),
+        };
+        let text = certificate.to_text(&board);
+
+        assert_eq!(Certificate::parse(&board, &text), Ok(certificate.clone()));
+        assert_eq!(certificate::check(&board, &start, &certificate), Ok(()));
+    }
+}
+
+#[test]
+fn solvable_levels_have_nothing_to_prove() {
+    for name in ["simplest.txt", "demo01.txt", "demo02.txt"] {
+        let (board, start) = parse(name);
+        assert!(matches!(prove(&board, &start), Proof::Solvable), "{}", name);
+    }
+}
+
+#[test]
+fn certificates_with_missing_states_are_rejected() {
+    let (board, start) = parse("demo01.txt");
+
+    // the start alone isn't closed under pushes
+    let certificate = Certificate {
+        dead_tiles: Vec::new(),
+        states: vec![board.normalize_player(&start)],
+    };
+    assert_eq!(
+        certificate::check(&board, &start, &certificate),
+        Err("State 1 has a push to a state that isn't in the certificate".to_string())
+    );
+
+    // a crate that's about to be pushed onto a goal isn't stuck
+    let (x, y) = board.iter_crates(&start).next().unwrap();
+    let certificate = Certificate {
+        dead_tiles: vec![(x, y)],
+        states: Vec::new(),
+    };
+    assert_eq!(
+        certificate::check(&board, &start, &certificate),
+        Err(format!("A crate on {},{} can still reach a goal", x, y))
+    );
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use common::parse;
+use pushcrate::search::{self, CheckpointConfig, Limits, Metric, Outcome, SearchConfig, TieBreak};
+
+/// A checkpoint file unique to this test, so tests running in parallel don't share one.
+fn checkpoint_path(test: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pushcrate-{}-{}.ckpt", test, std::process::id()))
+}
+
+fn config(path: &Path, nodes: Option<u64>) -> SearchConfig {
+    SearchConfig {
+        progress: false,
+        limits: Limits {
+            nodes,
+            ..Limits::default()
+        },
+        checkpoint: Some(CheckpointConfig {
+            path: path.to_path_buf(),
+            interval: Duration::from_secs(3600),
+        }),
+        ..SearchConfig::default()
+    }
+}
+
+#[test]
+fn resumed_search_matches_uninterrupted_search() {
+    for name in ["demo01.txt", "demo02.txt"] {
+        let (board, start) = parse(name);
+        let path = checkpoint_path(&format!("resume-{}", name));
+
+        let uninterrupted = search::find_path(&board, &start, &config(&path, None));
+
+        let stopped = search::find_path(&board, &start, &config(&path, Some(5)));
+        assert!(
+            matches!(stopped.outcome, Outcome::LimitReached(..)),
+            "{}",
+            name
+        );
+
+        // resume a few times over, saving again each time the limit is hit
+        let mut resumed = search::resume_path(&board, &start, &config(&path, Some(10))).unwrap();
+        while let Outcome::LimitReached(..) = resumed.outcome {
+            let nodes = resumed.stats.nodes_expanded + 5;
+            resumed = search::resume_path(&board, &start, &config(&path, Some(nodes))).unwrap();
+        }
+
+        assert!(uninterrupted.solution().is_some(), "{}", name);
+        assert_eq!(resumed.solution(), uninterrupted.solution(), "{}", name);
+        assert_eq!(
+            resumed.stats.nodes_expanded, uninterrupted.stats.nodes_expanded,
+            "{}",
+            name
+        );
+        assert_eq!(
+            resumed.stats.nodes_generated, uninterrupted.stats.nodes_generated,
+            "{}",
+            name
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn resume_rejects_checkpoints_for_other_searches() {
+    let (board, start) = parse("demo01.txt");
+    let (other_board, other_start) = parse("demo02.txt");
+    let path = checkpoint_path("mismatch");
+
+    search::find_path(&board, &start, &config(&path, Some(5)));
+
+    let error = search::resume_path(&other_board, &other_start, &config(&path, None))
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Checkpoint was saved for a different level"
+    );
+
+    let pushes = SearchConfig {
+        metric: Metric::Pushes,
+        ..config(&path, None)
+    };
+    let error = search::resume_path(&board, &start, &pushes).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Checkpoint was saved with a different metric"
+    );
+
+    let lifo = SearchConfig {
+        tie_break: TieBreak::Lifo,
+        ..config(&path, None)
+    };
+    let error = search::resume_path(&board, &start, &lifo).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Checkpoint was saved with a different tie-break"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn resume_needs_a_checkpoint() {
+    let (board, start) = parse("demo01.txt");
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    assert!(search::resume_path(&board, &start, &config).is_err());
+}
@@ -0,0 +1,43 @@
+// each test binary uses only some of these
+#![allow(dead_code)]
+
+use pushcrate::board::{Action, Board, BoardState};
+use pushcrate::search::Metric;
+
+/// Reads a fixture from the `test` directory.
+pub fn load(name: &str) -> String {
+    let path = format!("{}/test/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {}", path, e))
+}
+
+pub fn parse(name: &str) -> (Board, BoardState) {
+    Board::parse_level_string(&load(name)).unwrap_or_else(|e| panic!("parsing {}: {}", name, e))
+}
+
+/// Replays `path`, returning the final state and what it cost under `metric`.
+pub fn replay(
+    board: &Board,
+    start: &BoardState,
+    path: &[Action],
+    metric: Metric,
+) -> (BoardState, usize) {
+    let mut state = start.clone();
+    let mut pushes = 0;
+
+    for (i, action) in path.iter().enumerate() {
+        let (next, push) = board
+            .step(&state, *action)
+            .unwrap_or_else(|| panic!("illegal move {:?} at step {}", action, i));
+        state = next;
+        pushes += push as usize;
+    }
+
+    match metric {
+        Metric::Moves => (state, path.len()),
+        Metric::Pushes => (state, pushes),
+    }
+}
+
+pub fn solves(board: &Board, start: &BoardState, path: &[Action]) -> bool {
+    board.is_goal_state(&replay(board, start, path, Metric::Moves).0)
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{parse, replay};
+use pushcrate::external::{self, ExternalConfig};
+use pushcrate::search::{self, Metric, Outcome, SearchConfig};
+
+// small enough that every bucket is sorted in several runs and merged
+fn tiny_buffer() -> ExternalConfig {
+    ExternalConfig {
+        buffer: 64,
+        ..ExternalConfig::default()
+    }
+}
+
+#[test]
+fn external_search_finds_solutions_as_short_as_in_memory() {
+    for metric in [Metric::Moves, Metric::Pushes] {
+        let config = SearchConfig {
+            progress: false,
+            metric,
+            ..SearchConfig::default()
+        };
+
+        for name in [
+            "simplest.txt",
+            "solved.txt",
+            "demo01.txt",
+            "demo02.txt",
+            "demo03.txt",
+        ] {
+            let (board, start) = parse(name);
+
+            let in_memory = search::find_path(&board, &start, &config);
+            let on_disk = external::find_path(&board, &start, &config, &tiny_buffer()).unwrap();
+
+            let expected = replay(&board, &start, in_memory.solution().unwrap(), metric).1;
+            let path = on_disk
+                .solution()
+                .unwrap_or_else(|| panic!("{} was not solved", name));
+            let (state, cost) = replay(&board, &start, path, metric);
+
+            assert_eq!(cost, expected, "{} {:?}", name, metric);
+            assert!(board.is_goal_state(&state), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn external_search_exhausts_unsolvable_levels() {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    for name in ["unsolvable/1.txt", "unsolvable/2.txt", "unsolvable/3.txt"] {
+        let (board, start) = parse(name);
+        let result = external::find_path(&board, &start, &config, &tiny_buffer()).unwrap();
+        assert!(
+            matches!(result.outcome, Outcome::Unsolvable),
+            "{} was not reported unsolvable",
+            name
+        );
+    }
+}
+
+#[test]
+fn external_search_without_a_buffer_still_solves() {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+    let no_buffer = ExternalConfig {
+        buffer: 0,
+        ..ExternalConfig::default()
+    };
+
+    let (board, start) = parse("demo01.txt");
+    let result = external::find_path(&board, &start, &config, &no_buffer).unwrap();
+    let (state, _) = replay(&board, &start, result.solution().unwrap(), Metric::Moves);
+    assert!(board.is_goal_state(&state));
+}
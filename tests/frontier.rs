@@ -0,0 +1,90 @@
+mod common;
+
+use common::{parse, replay};
+use pushcrate::frontier::{self, FrontierConfig};
+use pushcrate::search::{self, Limit, Limits, Metric, Outcome, SearchConfig};
+
+#[test]
+fn frontier_search_finds_solutions_as_short_as_a_star() {
+    for metric in [Metric::Moves, Metric::Pushes] {
+        let config = SearchConfig {
+            progress: false,
+            metric,
+            ..SearchConfig::default()
+        };
+
+        // with no layers kept, only duplicates still waiting to be expanded are caught
+        for (name, layers) in [
+            ("simplest.txt", 2),
+            ("solved.txt", 2),
+            ("demo01.txt", 2),
+            ("demo02.txt", 0),
+            ("demo03.txt", 2),
+        ] {
+            let frontier = FrontierConfig { layers };
+            let (board, start) = parse(name);
+
+            let a_star = search::find_path(&board, &start, &config);
+            let found = frontier::find_path(&board, &start, &config, &frontier).unwrap();
+
+            let expected = replay(&board, &start, a_star.solution().unwrap(), metric).1;
+            let path = found
+                .solution()
+                .unwrap_or_else(|| panic!("{} was not solved", name));
+            let (state, cost) = replay(&board, &start, path, metric);
+
+            assert_eq!(cost, expected, "{} {:?}", name, metric);
+            assert!(board.is_goal_state(&state), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn frontier_search_exhausts_unsolvable_levels() {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    // the crate in 4.txt can be pushed around in circles, which no fixed number of
+    // layers kept is sure to catch
+    for name in [
+        "unsolvable/1.txt",
+        "unsolvable/2.txt",
+        "unsolvable/3.txt",
+        "unsolvable/4.txt",
+    ] {
+        let (board, start) = parse(name);
+        let result =
+            frontier::find_path(&board, &start, &config, &FrontierConfig::default()).unwrap();
+        assert!(
+            matches!(result.outcome, Outcome::Unsolvable),
+            "{} was not reported unsolvable",
+            name
+        );
+    }
+}
+
+#[test]
+fn frontier_search_stops_at_its_limit_while_rebuilding_a_partial_path() {
+    let config = SearchConfig {
+        progress: false,
+        limits: Limits {
+            nodes: Some(20),
+            ..Limits::default()
+        },
+        ..SearchConfig::default()
+    };
+
+    let (board, start) = parse("demo03.txt");
+    let result = frontier::find_path(&board, &start, &config, &FrontierConfig::default()).unwrap();
+
+    match result.outcome {
+        Outcome::LimitReached(Limit::Nodes, partial) => {
+            // rebuilding the partial path stops at the limit too, one state past it at most
+            replay(&board, &start, &partial.path, Metric::Moves);
+            assert!(result.stats.nodes_expanded <= 21);
+        }
+        outcome => panic!("demo03.txt was not stopped: {:?}", outcome),
+    }
+}
@@ -0,0 +1,51 @@
+use pushcrate::board::Board;
+use pushcrate::generate::{self, GenerateConfig};
+use pushcrate::rng::Rng;
+use pushcrate::search::Limits;
+
+fn config() -> GenerateConfig {
+    GenerateConfig {
+        width: 8,
+        height: 7,
+        crates: 2,
+        pushes: 4..=30,
+        scramble: 40,
+        limits: Limits {
+            nodes: Some(100_000),
+            ..Limits::default()
+        },
+    }
+}
+
+#[test]
+fn generated_levels_are_solvable_in_range() {
+    let config = config();
+    let mut rng = Rng::new(7);
+
+    for _ in 0..3 {
+        let level = generate::generate(&config, &mut rng, 200).expect("no level generated");
+        assert!(config.pushes.contains(&level.pushes));
+
+        let (board, start) = Board::parse_level_string(&level.level).unwrap();
+        assert!(!board.is_goal_state(&start));
+        assert_eq!(level.solution.len(), level.moves);
+    }
+}
+
+#[test]
+fn generation_is_reproducible_from_seed() {
+    let config = config();
+
+    let a = generate::generate(&config, &mut Rng::new(99), 200).unwrap();
+    let b = generate::generate(&config, &mut Rng::new(99), 200).unwrap();
+    assert_eq!(a.level, b.level);
+}
+
+#[test]
+fn crates_are_scrambled_off_goals_against_walls() {
+    let (board, solved) = Board::parse_level_string("######\n#@  *#\n######\n").unwrap();
+
+    let start = generate::scramble(&board, &solved, 2, &mut Rng::new(1)).unwrap();
+    assert!(!board.is_goal_state(&start));
+    assert_eq!(board.iter_crates(&start).collect::<Vec<_>>(), [(2, 1)]);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{parse, solves};
+use pushcrate::board::{Board, BoardState};
+use pushcrate::greedy::{self, BeamConfig};
+use pushcrate::search::{Limit, Outcome, SearchConfig, SearchResult};
+
+fn config() -> SearchConfig {
+    SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    }
+}
+
+const LEVELS: [&str; 5] = [
+    "simplest.txt",
+    "solved.txt",
+    "demo01.txt",
+    "demo02.txt",
+    "demo03.txt",
+];
+
+fn assert_solved(name: &str, board: &Board, start: &BoardState, result: &SearchResult) {
+    let path = result
+        .solution()
+        .unwrap_or_else(|| panic!("{} was not solved", name));
+    assert!(solves(board, start, path), "{}", name);
+}
+
+#[test]
+fn greedy_search_solves_levels() {
+    for name in LEVELS {
+        let (board, start) = parse(name);
+        let result = greedy::find_path(&board, &start, &config());
+        assert_solved(name, &board, &start, &result);
+    }
+}
+
+#[test]
+fn beam_search_solves_levels() {
+    for name in LEVELS {
+        let (board, start) = parse(name);
+        let result = greedy::beam_path(&board, &start, &config(), &BeamConfig::default());
+        assert_solved(name, &board, &start, &result);
+    }
+}
+
+#[test]
+fn beam_search_only_reports_levels_unsolvable_when_nothing_was_dropped() {
+    let (board, start) = parse("unsolvable/1.txt");
+    let result = greedy::beam_path(&board, &start, &config(), &BeamConfig::default());
+    assert!(matches!(result.outcome, Outcome::Unsolvable));
+
+    // a single state per depth runs into a dead end
+    let (board, start) = parse("demo02.txt");
+    let result = greedy::beam_path(&board, &start, &config(), &BeamConfig { width: 1 });
+    assert!(matches!(
+        result.outcome,
+        Outcome::LimitReached(Limit::Width, _)
+    ));
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::parse;
+use pushcrate::board::{Action, Board, BoardState};
+use pushcrate::heuristic::{
+    Guided, Heuristic, LinearConflict, Matching, Max, NearestGoal, PatternDatabase, PlayerDistance,
+};
+use pushcrate::search::{self, Metric, SearchConfig};
+
+/// Every state along `path`, each with what's left to pay from it under `metric`.
+fn costs_left(
+    board: &Board,
+    start: &BoardState,
+    path: &[Action],
+    metric: Metric,
+) -> Vec<(BoardState, u32)> {
+    let mut states = vec![(start.clone(), 0)];
+    for action in path {
+        let (next, push) = board.step(&states.last().unwrap().0, *action).unwrap();
+        let cost = match metric {
+            Metric::Moves => 1,
+            Metric::Pushes => push as u32,
+        };
+        states.push((next, cost));
+    }
+
+    let mut left = 0;
+    for (_, cost) in states.iter_mut().rev() {
+        left += std::mem::replace(cost, left);
+    }
+    states
+}
+
+fn heuristics(board: &Board) -> Vec<(&'static str, Box<dyn Heuristic>)> {
+    vec![
+        ("nearest", Box::new(NearestGoal)),
+        ("matching", Box::new(Matching)),
+        ("conflict", Box::new(LinearConflict)),
+        ("player", Box::new(PlayerDistance)),
+        ("pdb", Box::new(PatternDatabase::new(board))),
+        (
+            "max",
+            Box::new(Max(vec![Box::new(Matching), Box::new(PlayerDistance)])),
+        ),
+    ]
+}
+
+const LEVELS: [&str; 5] = [
+    "simplest.txt",
+    "solved.txt",
+    "demo01.txt",
+    "demo02.txt",
+    "demo03.txt",
+];
+
+#[test]
+fn heuristics_never_overestimate_along_the_cheapest_solution() {
+    for metric in [Metric::Moves, Metric::Pushes] {
+        let config = SearchConfig {
+            progress: false,
+            metric,
+            ..SearchConfig::default()
+        };
+
+        for name in LEVELS {
+            let (board, start) = parse(name);
+            let path = search::find_path(&board, &start, &config)
+                .solution()
+                .unwrap()
+                .clone();
+
+            for (heuristic_name, heuristic) in heuristics(&board) {
+                for (state, left) in costs_left(&board, &start, &path, metric) {
+                    let estimate = heuristic.estimate(&board, &state, metric);
+                    assert!(
+                        estimate <= left,
+                        "{} overestimates on {} ({} > {})",
+                        heuristic_name,
+                        name,
+                        estimate,
+                        left
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn search_with_any_heuristic_finds_the_cheapest_solution() {
+    for metric in [Metric::Moves, Metric::Pushes] {
+        let config = SearchConfig {
+            progress: false,
+            metric,
+            ..SearchConfig::default()
+        };
+
+        for name in LEVELS {
+            let (board, start) = parse(name);
+            let expected = search::find_path(&board, &start, &config)
+                .solution()
+                .map(|path| costs_left(&board, &start, path, metric)[0].1);
+
+            for (heuristic_name, heuristic) in heuristics(&board) {
+                let guided = Guided {
+                    board: &board,
+                    heuristic,
+                    metric,
+                };
+                let found = search::find_path(&guided, &start, &config)
+                    .solution()
+                    .map(|path| costs_left(&board, &start, path, metric)[0].1);
+
+                assert_eq!(found, expected, "{} on {}", heuristic_name, name);
+            }
+        }
+    }
+}
+
+#[test]
+fn matching_is_never_below_the_nearest_goal_sum() {
+    for name in LEVELS {
+        let (board, start) = parse(name);
+        let nearest = NearestGoal.estimate(&board, &start, Metric::Pushes);
+        let matching = Matching.estimate(&board, &start, Metric::Pushes);
+        assert!(matching >= nearest, "{}", name);
+    }
+}
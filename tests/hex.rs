@@ -0,0 +1,95 @@
+mod common;
+
+use common::load;
+use pushcrate::grid::{Direction, GridState};
+use pushcrate::hex::HexBoard;
+use pushcrate::search::{self, Outcome, SearchConfig};
+
+fn parse(name: &str) -> (HexBoard, GridState) {
+    HexBoard::parse_level_string(&load(&format!("hex/{}", name)))
+        .unwrap_or_else(|e| panic!("parsing {}: {}", name, e))
+}
+
+fn solve(board: &HexBoard, start: &GridState) -> Outcome<Direction> {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    search::find_path(board, start, &config).outcome
+}
+
+#[test]
+fn solvable_levels_replay_to_optimal_solutions() {
+    // (level, optimal moves, optimal pushes)
+    let known = [
+        ("simplest.txt", 1, 1),
+        ("demo01.txt", 5, 2),
+        ("demo02.txt", 8, 4),
+    ];
+
+    for (name, moves, pushes) in known {
+        let (board, start) = parse(name);
+
+        let path = match solve(&board, &start) {
+            Outcome::Solved(path) => path,
+            outcome => panic!("{} was not solved: {:?}", name, outcome),
+        };
+
+        let mut state = start.clone();
+        let mut pushed = 0;
+        for action in path.iter() {
+            let (next, push) = board.step(&state, *action).expect("illegal move");
+            state = next;
+            pushed += push as usize;
+        }
+
+        assert!(
+            board.is_goal_state(&state),
+            "{} solution does not reach the goal",
+            name
+        );
+        assert_eq!((path.len(), pushed), (moves, pushes), "{}", name);
+    }
+}
+
+#[test]
+fn crate_against_wall_is_dead() {
+    let (board, start) = parse("unsolvable.txt");
+
+    assert!(board.render(&start).contains('!'));
+    assert!(matches!(solve(&board, &start), Outcome::Unsolvable));
+}
+
+#[test]
+fn moves_are_written_with_hex_letters() {
+    let (board, start) = parse("demo01.txt");
+    let path = [
+        Direction::East,
+        Direction::NorthWest,
+        Direction::East,
+        Direction::NorthEast,
+        Direction::SouthEast,
+    ];
+
+    assert_eq!(board.format(&start, &path), "lYluN");
+}
+
+#[test]
+fn invalid_levels_are_rejected() {
+    let expected = [
+        (" # # #\n#  @ #\n # # #\n", "Level has a cell off the grid"),
+        (" # # #\n# @ .\n # # #\n", "Player is not enclosed in walls"),
+        (
+            " # # # #\n# @ $ #\n # # # #\n",
+            "Number of crates and number of goals are not the same",
+        ),
+    ];
+
+    for (level, error) in expected {
+        match HexBoard::parse_level_string(level) {
+            Ok(_) => panic!("{:?} parsed successfully", level),
+            Err(e) => assert_eq!(e, error, "{:?}", level),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::{load, parse, replay};
+use pushcrate::board::{Board, BoardState};
+use pushcrate::search::{self, Metric, Outcome, SearchConfig, TieBreak};
+
+fn solve(board: &Board, start: &BoardState) -> Outcome {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    search::find_path(board, start, &config).outcome
+}
+
+/// Solves a fixture, checks the solution actually solves it, and returns its move and push counts.
+fn solve_and_replay(name: &str) -> (usize, usize) {
+    let (board, start) = parse(name);
+
+    match solve(&board, &start) {
+        Outcome::Solved(path) => {
+            let (end, pushes) = replay(&board, &start, &path, Metric::Pushes);
+            assert!(
+                board.is_goal_state(&end),
+                "{} solution does not reach the goal",
+                name
+            );
+            (path.len(), pushes)
+        }
+        outcome => panic!("{} was not solved: {:?}", name, outcome),
+    }
+}
+
+#[test]
+fn solvable_levels_replay_to_optimal_solutions() {
+    // (level, optimal moves, optimal pushes)
+    let known = [
+        ("simplest.txt", 1, 1),
+        ("solved.txt", 0, 0),
+        ("demo01.txt", 10, 4),
+        ("demo02.txt", 14, 7),
+        ("demo03.txt", 11, 4),
+    ];
+
+    for (name, moves, pushes) in known {
+        assert_eq!(solve_and_replay(name), (moves, pushes), "{}", name);
+    }
+}
+
+#[test]
+fn every_tie_break_finds_optimal_solutions() {
+    let (board, start) = parse("demo02.txt");
+    let mut expanded = Vec::new();
+
+    for tie_break in [
+        TieBreak::Fifo,
+        TieBreak::Lifo,
+        TieBreak::LowerH,
+        TieBreak::MoreOnGoals,
+    ] {
+        let config = SearchConfig {
+            progress: false,
+            tie_break,
+            ..SearchConfig::default()
+        };
+        let result = search::find_path(&board, &start, &config);
+
+        assert_eq!(result.solution().map(Vec::len), Some(14), "{:?}", tie_break);
+        expanded.push(result.stats.nodes_expanded);
+    }
+
+    // going deepest first among equals finds the goal sooner than taking them in turn
+    assert!(expanded[2] < expanded[0], "{:?}", expanded);
+}
+
+#[test]
+fn unsolvable_levels_are_exhausted() {
+    for name in [
+        "unsolvable/1.txt",
+        "unsolvable/2.txt",
+        "unsolvable/3.txt",
+        "unsolvable/4.txt",
+    ] {
+        let (board, start) = parse(name);
+        assert!(
+            matches!(solve(&board, &start), Outcome::Unsolvable),
+            "{} was not reported unsolvable",
+            name
+        );
+    }
+}
+
+#[test]
+fn invalid_levels_are_rejected() {
+    let expected = [
+        ("open.txt", "Player is not enclosed in walls"),
+        ("invalid/empty.txt", "Level is empty"),
+        (
+            "invalid/invalid_character.txt",
+            "Level contains invalid character",
+        ),
+        ("invalid/no_player.txt", "Level has no player"),
+        ("invalid/two_players.txt", "Level has more than one player"),
+        (
+            "invalid/crate_goal_mismatch.txt",
+            "Number of crates and number of goals are not the same",
+        ),
+    ];
+
+    for (name, error) in expected {
+        match Board::parse_level_string(&load(name)) {
+            Ok(_) => panic!("{} parsed successfully", name),
+            Err(e) => assert_eq!(e, error, "{}", name),
+        }
+    }
+}
+
+#[test]
+fn xsokoban_screens_parse() {
+    for i in 1..=90 {
+        parse(&format!("xsokoban/screen.{:02}", i));
+    }
+}
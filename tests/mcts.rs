@@ -0,0 +1,73 @@
+mod common;
+
+use common::{parse, solves};
+use pushcrate::mcts::{self, MctsConfig, Rollout};
+use pushcrate::search::{Limits, Outcome, SearchConfig};
+
+fn config() -> SearchConfig {
+    SearchConfig {
+        progress: false,
+        limits: Limits {
+            nodes: Some(100_000),
+            ..Limits::default()
+        },
+        ..SearchConfig::default()
+    }
+}
+
+#[test]
+fn mcts_solves_levels_with_either_rollout() {
+    for rollout in [Rollout::Random, Rollout::Guided] {
+        for name in [
+            "simplest.txt",
+            "solved.txt",
+            "demo01.txt",
+            "demo02.txt",
+            "demo03.txt",
+        ] {
+            let (board, start) = parse(name);
+            let mcts = MctsConfig {
+                rollout,
+                ..MctsConfig::default()
+            };
+
+            let result = mcts::find_path(&board, &start, &config(), &mcts);
+            let path = result
+                .solution()
+                .unwrap_or_else(|| panic!("{} was not solved with {:?}", name, rollout));
+            assert!(solves(&board, &start, path), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn mcts_is_reproducible_from_its_seed() {
+    let (board, start) = parse("demo03.txt");
+    // rollouts too short to reach a goal, so the tree has to grow
+    let mcts = MctsConfig {
+        seed: 42,
+        rollout: Rollout::Random,
+        depth: 1,
+        ..MctsConfig::default()
+    };
+
+    let first = mcts::find_path(&board, &start, &config(), &mcts);
+    let second = mcts::find_path(&board, &start, &config(), &mcts);
+
+    assert!(first.solution().is_some());
+    assert_eq!(first.solution(), second.solution());
+    assert_eq!(first.stats.nodes_expanded, second.stats.nodes_expanded);
+}
+
+#[test]
+fn mcts_reports_unsolvable_levels_once_the_tree_is_exhausted() {
+    for name in ["unsolvable/1.txt", "unsolvable/2.txt", "unsolvable/3.txt"] {
+        let (board, start) = parse(name);
+        let result = mcts::find_path(&board, &start, &config(), &MctsConfig::default());
+        assert!(
+            matches!(result.outcome, Outcome::Unsolvable),
+            "{} was not reported unsolvable",
+            name
+        );
+    }
+}
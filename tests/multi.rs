@@ -0,0 +1,89 @@
+mod common;
+
+use common::load;
+use pushcrate::multi::{MultiBoard, MultiState};
+use pushcrate::search::{self, Metric, SearchConfig};
+
+fn parse(name: &str) -> (MultiBoard, MultiState) {
+    MultiBoard::parse_level_string(&load(&format!("multi/{}", name)))
+        .unwrap_or_else(|e| panic!("parsing {}: {}", name, e))
+}
+
+/// Solves a fixture, checks the solution replays to the goal and returns it formatted.
+fn solve(name: &str) -> String {
+    let (board, start) = parse(name);
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    let result = search::find_path(&board, &start, &config);
+    let path = result
+        .solution()
+        .unwrap_or_else(|| panic!("{} was not solved: {:?}", name, result.outcome));
+
+    let end = path.iter().fold(start.clone(), |state, step| {
+        board.step(&state, *step).expect("illegal move").0
+    });
+    assert!(
+        board.is_goal_state(&end),
+        "{} solution does not reach the goal",
+        name
+    );
+
+    board.format(&start, path)
+}
+
+#[test]
+fn each_player_pushes_in_their_own_region() {
+    assert_eq!(solve("separate.txt"), "1:R 2:L");
+}
+
+#[test]
+fn players_step_out_of_each_others_way() {
+    assert_eq!(solve("blocking.txt"), "1:R 2:d 1:RR");
+}
+
+#[test]
+fn players_block_pushes() {
+    let (board, start) = parse("blocking.txt");
+    assert_eq!(board.players(&start), [(1, 1), (4, 1)]);
+
+    // the crate can go one tile before the second player is in the way
+    let children = board.create_children_counted(&start, &mut Default::default());
+    let pushes = children
+        .iter()
+        .filter(|(_, moves)| moves.iter().all(|step| step.player == 0))
+        .count();
+    assert_eq!(pushes, 1);
+}
+
+#[test]
+fn stepping_aside_costs_no_pushes() {
+    let (board, start) = parse("blocking.txt");
+
+    for metric in [Metric::Moves, Metric::Pushes] {
+        let config = SearchConfig {
+            progress: false,
+            metric,
+            ..SearchConfig::default()
+        };
+
+        let result = search::find_path(&board, &start, &config);
+        let path = result.solution().unwrap();
+
+        let mut state = start.clone();
+        let mut pushes = 0;
+        for step in path {
+            let (next, push) = board.step(&state, *step).expect("illegal move");
+            state = next;
+            pushes += push as u32;
+        }
+
+        let cost = match metric {
+            Metric::Moves => path.len() as u32,
+            Metric::Pushes => pushes,
+        };
+        assert_eq!(result.stats.solution_cost, Some(cost), "{:?}", metric);
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{parse, solves};
+use pushcrate::board::Board;
+use pushcrate::lurd;
+use pushcrate::optimize::{self, OptimizeConfig};
+use pushcrate::search::{self, Metric, SearchConfig};
+
+#[test]
+fn removes_push_cycles_and_detours() {
+    let (board, start) = parse("demo01.txt");
+
+    // pushes a crate right and back again, then wanders back to the start
+    // before playing the optimal solution
+    let path = lurd::parse("rRurrdLullldurrDrDLulD").unwrap();
+    assert!(solves(&board, &start, &path));
+
+    let optimized = optimize::optimize(&board, &start, &path, &OptimizeConfig::default()).unwrap();
+    assert_eq!(optimized.cycle_pushes, 2);
+    assert_eq!(optimized.path.len(), 10);
+    assert!(solves(&board, &start, &optimized.path));
+}
+
+#[test]
+fn windows_shorten_push_optimal_solutions() {
+    let level =
+        "#########\n######  #\n###@$   #\n####    #\n####  $ #\n#### .$.#\n####.   #\n#########\n";
+    let (board, start) = Board::parse_level_string(level).unwrap();
+
+    let config = SearchConfig {
+        progress: false,
+        metric: Metric::Pushes,
+        ..SearchConfig::default()
+    };
+    let path = search::find_path(&board, &start, &config)
+        .solution()
+        .unwrap()
+        .clone();
+
+    let optimized = optimize::optimize(&board, &start, &path, &OptimizeConfig::default()).unwrap();
+    assert!(optimized.improved_windows > 0);
+    assert!(optimized.path.len() < path.len());
+    assert!(solves(&board, &start, &optimized.path));
+}
+
+#[test]
+fn rejects_solutions_that_dont_solve() {
+    let (board, start) = parse("demo01.txt");
+    let config = OptimizeConfig::default();
+
+    let unfinished = lurd::parse("urrD").unwrap();
+    assert!(optimize::optimize(&board, &start, &unfinished, &config).is_err());
+
+    let illegal = lurd::parse("l").unwrap();
+    assert!(optimize::optimize(&board, &start, &illegal, &config).is_err());
+}
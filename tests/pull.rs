@@ -0,0 +1,105 @@
+mod common;
+
+use pushcrate::board::BoardState;
+use pushcrate::pull::{PullBoard, PullMove, Rules};
+use pushcrate::search::{self, Outcome, SearchConfig};
+
+fn parse(name: &str, rules: Rules) -> (PullBoard, BoardState) {
+    let (board, start) = common::parse(name);
+    (PullBoard::new(board, rules), start)
+}
+
+/// Solves a fixture, checks the solution replays to the goal and returns its cost under `rules`.
+fn solve_and_replay(name: &str, rules: Rules) -> u32 {
+    let (board, start) = parse(name, rules);
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    let path: Vec<PullMove> = match search::find_path(&board, &start, &config).outcome {
+        Outcome::Solved(path) => path,
+        outcome => panic!("{} was not solved: {:?}", name, outcome),
+    };
+
+    let end = path.iter().fold(start, |state, step| {
+        board.step(&state, *step).expect("illegal move").0
+    });
+    assert!(
+        board.is_goal_state(&end),
+        "{} solution does not reach the goal",
+        name
+    );
+
+    path.iter()
+        .map(|step| if step.pull { rules.pull_cost } else { 1 })
+        .sum()
+}
+
+#[test]
+fn pulls_free_crates_from_corners() {
+    let rules = Rules::default();
+
+    // push-only dead tiles don't apply, so both are solvable
+    assert_eq!(solve_and_replay("pull/corner.txt", rules), 8);
+    assert_eq!(solve_and_replay("unsolvable/1.txt", rules), 7);
+}
+
+#[test]
+fn pull_cost_is_weighed_against_pushing() {
+    // (level, cost with pulls at 1, cost with pulls at 5)
+    let known = [
+        ("pull/corner.txt", 8, 12),
+        ("demo01.txt", 10, 10),
+        ("demo02.txt", 13, 14),
+    ];
+
+    for (name, cheap, dear) in known {
+        let rules = Rules::default();
+        assert_eq!(solve_and_replay(name, rules), cheap, "{}", name);
+
+        let rules = Rules {
+            pull_cost: 5,
+            ..Rules::default()
+        };
+        assert_eq!(solve_and_replay(name, rules), dear, "{}", name);
+    }
+}
+
+#[test]
+fn pushes_only_keeps_corners_dead() {
+    let rules = Rules {
+        pulls: false,
+        ..Rules::default()
+    };
+    let (board, start) = parse("pull/corner.txt", rules);
+
+    assert!(board.render(&start).contains('!'));
+    assert!(matches!(
+        search::find_path(
+            &board,
+            &start,
+            &SearchConfig {
+                progress: false,
+                ..SearchConfig::default()
+            }
+        )
+        .outcome,
+        Outcome::Unsolvable
+    ));
+}
+
+#[test]
+fn pulls_are_marked_in_lurd() {
+    let (board, start) = parse("pull/corner.txt", Rules::default());
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    let result = search::find_path(&board, &start, &config);
+    assert_eq!(
+        board.format(&start, result.solution().unwrap()),
+        "ru~RdlluR"
+    );
+}
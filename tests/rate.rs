@@ -0,0 +1,33 @@
+mod common;
+
+use common::parse;
+use pushcrate::rate;
+use pushcrate::search::SearchConfig;
+
+fn rate(name: &str) -> Option<rate::Rating> {
+    let (board, start) = parse(name);
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    rate::rate(&board, &start, &config)
+}
+
+#[test]
+fn harder_levels_rate_higher() {
+    let simplest = rate("simplest.txt").unwrap();
+    let demo01 = rate("demo01.txt").unwrap();
+    let demo02 = rate("demo02.txt").unwrap();
+
+    assert!(simplest.score < demo01.score);
+    assert!(demo01.score < demo02.score);
+
+    assert_eq!((demo02.moves, demo02.pushes), (14, 7));
+    assert!((0.0..=1.0).contains(&demo02.greedy_deviation));
+}
+
+#[test]
+fn unsolvable_levels_are_not_rated() {
+    assert!(rate("unsolvable/1.txt").is_none());
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::parse;
+use pushcrate::search::{self, Observer, SearchConfig, SearchStats};
+use pushcrate::{anytime, greedy, mcts};
+
+fn config() -> SearchConfig {
+    SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    }
+}
+
+#[test]
+fn observer_sees_every_interval_and_the_finish() {
+    let (board, start) = parse("demo02.txt");
+    let seen: Arc<Mutex<Vec<u64>>> = Arc::default();
+
+    let observed = seen.clone();
+    let config = SearchConfig {
+        progress_interval: 10,
+        observer: Some(Observer::new(move |stats: &SearchStats| {
+            observed.lock().unwrap().push(stats.nodes_expanded)
+        })),
+        ..config()
+    };
+    let result = search::find_path(&board, &start, &config);
+
+    let seen = seen.lock().unwrap();
+    let expanded = result.stats.nodes_expanded;
+    let mut expected: Vec<u64> = (1..=expanded / 10).map(|i| i * 10).collect();
+    expected.push(expanded);
+    assert_eq!(*seen, expected);
+}
+
+#[test]
+fn stats_break_down_a_solved_search() {
+    let (board, start) = parse("demo02.txt");
+    let stats = search::find_path(&board, &start, &config()).stats;
+
+    assert!(stats.nodes_generated >= stats.nodes_expanded - 1);
+    assert!(stats.duplicates > 0);
+    assert!(stats.peak_open > 0);
+    assert!(stats.branching_factor() > 0.0);
+    assert_eq!(
+        stats.prunes.total(),
+        stats.prunes.dead_tile + stats.prunes.deadlock + stats.prunes.other
+    );
+
+    let accuracy = stats.heuristic_accuracy().unwrap();
+    assert!(accuracy > 0.0 && accuracy <= 1.0, "{}", accuracy);
+}
+
+#[test]
+fn every_engine_records_the_solution_cost() {
+    let (board, start) = parse("demo01.txt");
+    let config = config();
+
+    let results = [
+        ("astar", search::find_path(&board, &start, &config).stats),
+        (
+            "anytime",
+            anytime::find_path(&board, &start, &config, |_| ()).stats,
+        ),
+        ("greedy", greedy::find_path(&board, &start, &config).stats),
+        (
+            "mcts",
+            mcts::find_path(&board, &start, &config, &Default::default()).stats,
+        ),
+    ];
+
+    for (name, stats) in results {
+        assert!(stats.solution_cost.is_some(), "{}", name);
+        assert!(
+            stats.start_heuristic <= stats.solution_cost.unwrap(),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn unsolved_searches_have_no_heuristic_accuracy() {
+    let (board, start) = parse("unsolvable/1.txt");
+    let stats = search::find_path(&board, &start, &config()).stats;
+    assert_eq!(stats.solution_cost, None);
+    assert_eq!(stats.heuristic_accuracy(), None);
+}
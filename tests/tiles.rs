@@ -0,0 +1,97 @@
+mod common;
+
+use common::load;
+use pushcrate::board::Action;
+use pushcrate::search::{self, Outcome, SearchConfig};
+use pushcrate::tiles::{TileBoard, TileState};
+
+fn parse(name: &str) -> (TileBoard, TileState) {
+    TileBoard::parse_level_string(&load(&format!("tiles/{}", name)))
+        .unwrap_or_else(|e| panic!("parsing {}: {}", name, e))
+}
+
+fn solve(board: &TileBoard, start: &TileState) -> Outcome<Action> {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    search::find_path(board, start, &config).outcome
+}
+
+#[test]
+fn solvable_levels_replay_to_optimal_solutions() {
+    // (level, optimal moves, optimal pushes)
+    // blocker.txt can only be solved by sliding a crate up against another on the ice
+    let known = [
+        ("blocker.txt", 7, 3),
+        ("hole.txt", 3, 2),
+        ("ice.txt", 6, 2),
+        ("oneway.txt", 13, 7),
+        ("teleport.txt", 7, 4),
+    ];
+
+    for (name, moves, pushes) in known {
+        let (board, start) = parse(name);
+
+        let path = match solve(&board, &start) {
+            Outcome::Solved(path) => path,
+            outcome => panic!("{} was not solved: {:?}", name, outcome),
+        };
+
+        let mut state = start.clone();
+        let mut pushed = 0;
+        for action in path.iter() {
+            let (next, push) = board.step(&state, *action).expect("illegal move");
+            state = next;
+            pushed += push as usize;
+        }
+
+        assert!(
+            board.is_goal_state(&state),
+            "{} solution does not reach the goal",
+            name
+        );
+        assert_eq!((path.len(), pushed), (moves, pushes), "{}", name);
+    }
+}
+
+#[test]
+fn filled_holes_are_floor() {
+    let (board, start) = parse("hole.txt");
+
+    assert_eq!(board.render(&start), "#######\n#%$o$.#\n#######\n");
+
+    let (next, push) = board.step(&start, Action::Right).unwrap();
+    assert!(push);
+    assert_eq!(board.render(&next), "#######\n#-@ $.#\n#######\n");
+}
+
+#[test]
+fn crates_slide_across_ice() {
+    let (board, start) = parse("ice.txt");
+
+    let (next, _) = board.step(&start, Action::Right).unwrap();
+    assert_eq!(
+        board.render(&next),
+        "########\n#- .  -#\n#-@==$##\n#------#\n########\n"
+    );
+}
+
+#[test]
+fn invalid_levels_are_rejected() {
+    let expected = [
+        (
+            "#####\n#@o.#\n#####\n",
+            "Number of crates is not the number of goals plus holes",
+        ),
+        ("######\n#@$1.#\n######\n", "Teleports must come in pairs"),
+    ];
+
+    for (level, error) in expected {
+        match TileBoard::parse_level_string(level) {
+            Ok(_) => panic!("{:?} parsing succeeded", level),
+            Err(e) => assert_eq!(e, error, "{:?}", level),
+        }
+    }
+}
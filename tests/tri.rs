@@ -0,0 +1,78 @@
+mod common;
+
+use common::load;
+use pushcrate::grid::{Direction, GridState};
+use pushcrate::search::{self, Outcome, SearchConfig};
+use pushcrate::tri::TriBoard;
+
+fn parse(name: &str) -> (TriBoard, GridState) {
+    TriBoard::parse_level_string(&load(&format!("tri/{}", name)))
+        .unwrap_or_else(|e| panic!("parsing {}: {}", name, e))
+}
+
+fn solve(board: &TriBoard, start: &GridState) -> Outcome<Direction> {
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    search::find_path(board, start, &config).outcome
+}
+
+#[test]
+fn solvable_levels_replay_to_optimal_solutions() {
+    // (level, optimal moves, optimal pushes)
+    let known = [
+        ("simplest.txt", 1, 1),
+        ("demo01.txt", 5, 3),
+        ("demo02.txt", 6, 5),
+    ];
+
+    for (name, moves, pushes) in known {
+        let (board, start) = parse(name);
+
+        let path = match solve(&board, &start) {
+            Outcome::Solved(path) => path,
+            outcome => panic!("{} was not solved: {:?}", name, outcome),
+        };
+
+        let mut state = start.clone();
+        let mut pushed = 0;
+        for action in path.iter() {
+            let (next, push) = board.step(&state, *action).expect("illegal move");
+            state = next;
+            pushed += push as usize;
+        }
+
+        assert!(
+            board.is_goal_state(&state),
+            "{} solution does not reach the goal",
+            name
+        );
+        assert_eq!((path.len(), pushed), (moves, pushes), "{}", name);
+    }
+}
+
+#[test]
+fn crate_against_wall_is_dead() {
+    let (board, start) = parse("unsolvable.txt");
+
+    assert!(board.render(&start).contains('!'));
+    assert!(matches!(solve(&board, &start), Outcome::Unsolvable));
+}
+
+#[test]
+fn diagonal_pushes_alternate_between_cell_shapes() {
+    // the crate starts in an up triangle, drops out of its bottom edge into the down
+    // triangle below and then carries on south west into the up triangle on the goal
+    let level = "#####\n#   #\n# $@#\n#.  #\n#####\n";
+    let (board, start) = TriBoard::parse_level_string(level).unwrap();
+
+    let (state, push) = board.step(&start, Direction::SouthWest).unwrap();
+    assert!(push);
+    assert_eq!(board.render(&state), "#####\n#   #\n# @ #\n#.$-#\n#####\n");
+
+    let (state, push) = board.step(&state, Direction::SouthWest).unwrap();
+    assert!(push);
+    assert!(board.is_goal_state(&state));
+}
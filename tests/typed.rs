@@ -0,0 +1,75 @@
+mod common;
+
+use common::load;
+use pushcrate::board::Action;
+use pushcrate::search::{self, Outcome, SearchConfig};
+use pushcrate::typed::{TypedBoard, TypedState};
+
+fn parse(name: &str) -> (TypedBoard, TypedState) {
+    TypedBoard::parse_level_string(&load(name))
+        .unwrap_or_else(|e| panic!("parsing {}: {}", name, e))
+}
+
+/// Solves a fixture, checks the solution replays to the goal and returns its move and push counts.
+fn solve_and_replay(name: &str) -> (usize, usize) {
+    let (board, start) = parse(name);
+    let config = SearchConfig {
+        progress: false,
+        ..SearchConfig::default()
+    };
+
+    let path = match search::find_path(&board, &start, &config).outcome {
+        Outcome::Solved(path) => path,
+        outcome => panic!("{} was not solved: {:?}", name, outcome),
+    };
+
+    let mut state = start;
+    let mut pushes = 0;
+    for action in path.iter() {
+        let (next, push) = board.step(&state, *action).expect("illegal move");
+        state = next;
+        pushes += push as usize;
+    }
+
+    assert!(
+        board.is_goal_state(&state),
+        "{} solution does not reach the goal",
+        name
+    );
+    (path.len(), pushes)
+}
+
+#[test]
+fn crates_go_to_goals_of_their_colour() {
+    // (level, optimal moves, optimal pushes)
+    let known = [
+        ("typed/crossed.txt", 19, 8),
+        ("typed/mixed.txt", 23, 7),
+        // plain levels are solved just like the usual variant
+        ("demo01.txt", 10, 4),
+    ];
+
+    for (name, moves, pushes) in known {
+        assert_eq!(solve_and_replay(name), (moves, pushes), "{}", name);
+    }
+}
+
+#[test]
+fn wrong_colour_on_goal_is_not_solved() {
+    let (board, start) =
+        TypedBoard::parse_level_string("######\n#@c D#\n# dC #\n######\n").unwrap();
+    let (state, _) = board.step(&start, Action::Right).unwrap();
+    let (state, push) = board.step(&state, Action::Right).unwrap();
+
+    assert!(push);
+    assert!(!board.is_goal_state(&state));
+    assert_eq!(board.render(&state), "######\n#- @c#\n#-dC-#\n######\n");
+}
+
+#[test]
+fn colours_must_pair_up() {
+    assert_eq!(
+        TypedBoard::parse_level_string("#####\n#@cD#\n#####\n").err(),
+        Some("Number of crates and number of goals of a colour are not the same")
+    );
+}